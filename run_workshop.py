@@ -1,4 +1,5 @@
 import argparse
+import json
 import math
 import subprocess
 import sys
@@ -24,6 +25,9 @@ init(autoreset=True)
 PYTHON_LIB_PATH = Path("./python_lib")
 RUST_LIB_PATH = Path("./rust_lib")
 
+# where `cargo bench` stores the results of the criterion benchmarks
+CRITERION_PATH = RUST_LIB_PATH / "target" / "criterion"
+
 
 def install_package(path, is_rust=False):
     """Installs a package in editable mode (or via maturin for Rust)."""
@@ -61,6 +65,43 @@ def load_workshop_config():
     return config
 
 
+def run_criterion_benchmark(benchmark_id):
+    """Runs a single criterion benchmark from rust_lib/benches."""
+    print(f"Running criterion benchmark {f'{benchmark_id}...':<13}", end="", flush=True)
+
+    cmd = [
+        "cargo",
+        "bench",
+        "--manifest-path",
+        str(RUST_LIB_PATH / "Cargo.toml"),
+        "--bench",
+        "fibonacci",
+        "--",
+        f"^{benchmark_id}$",
+    ]
+    result = subprocess.run(cmd, capture_output=True, text=True)
+
+    if result.returncode != 0:
+        print(f" {Fore.RED}FAILED")
+        print(f"{Fore.RED}Error details:\n{result.stderr}")
+        sys.exit(1)
+
+    print(f" {Fore.GREEN}DONE")
+
+
+def load_criterion_estimate(benchmark_id):
+    """Loads the mean time of a criterion benchmark in seconds, or None if it has not been run yet."""
+    path = CRITERION_PATH / benchmark_id / "new" / "estimates.json"
+    try:
+        with open(path) as file:
+            estimates = json.load(file)
+    except FileNotFoundError:
+        return None
+
+    # criterion measures in nanoseconds
+    return estimates["mean"]["point_estimate"] * 1e-9
+
+
 def get_time_scale(seconds):
     """Finds the best unit/multiplier based on the duration."""
     if seconds == 0:
@@ -163,7 +204,32 @@ def benchmark(
     return avg_time, unit, multiplier
 
 
-def main(benchmark_duration=1.0):
+def report_ffi_overhead(benchmark_id, rs_time, py_time, unit, multiplier):
+    """Compares the pure Rust criterion results to the calls through pyo3 and Python."""
+    pure_time = load_criterion_estimate(benchmark_id)
+    if pure_time is None:
+        print(
+            f"\n{Fore.YELLOW}No results for the criterion benchmark '{benchmark_id}' yet, "
+            f"run `cargo bench --bench fibonacci` in {RUST_LIB_PATH} or pass --criterion"
+        )
+        return
+
+    print(f"\n{Style.BRIGHT}{Fore.CYAN}=== FFI Overhead ===")
+    for name, seconds in [
+        ("Pure Rust", pure_time),
+        ("Rust via pyo3", rs_time),
+        ("Python", py_time),
+    ]:
+        print(f"    {name:<14}{seconds / multiplier:>14.3f} {unit}")
+
+    overhead = rs_time - pure_time
+    print(
+        f"\nCalling Rust from Python adds {Style.BRIGHT}{overhead / multiplier:.3f} {unit}{Style.RESET_ALL} "
+        f"per call, {overhead / rs_time * 100:.1f}% of the time spent in the Rust implementation"
+    )
+
+
+def main(benchmark_duration=1.0, run_criterion=False):
     print(f"{Style.BRIGHT}{Fore.CYAN}=== Install Phase ===")
 
     # Install/Build both libraries
@@ -241,6 +307,33 @@ def main(benchmark_duration=1.0):
             f"\nThe rust implementation ran {Fore.RED}{Style.BRIGHT}{slowdown:.2f}x slower{Style.RESET_ALL} than Python!"
         )
 
+    # `ffi_noop` takes and returns the same types as `implementation`, so it only measures the call itself
+    try:
+        config.do_work(rust_lib.ffi_noop)
+    except (TypeError, OverflowError):
+        print(f"\n{Fore.YELLOW}The workload does not fit `ffi_noop`, skipping the call overhead")
+    else:
+        print()
+        call_time, _, _ = benchmark(
+            "Rust call overhead",
+            rust_lib.ffi_noop,
+            config.do_work,
+            force_unit=used_unit,
+            force_multiplier=used_mult,
+            target_total_duration=benchmark_duration,
+        )
+        print(
+            f"\nWithout the call overhead, the rust implementation takes "
+            f"{Style.BRIGHT}{(rs_time - call_time) / used_mult:.3f} {used_unit}{Style.RESET_ALL} per call"
+        )
+
+    # the config can name a criterion benchmark which does the same work in pure Rust
+    benchmark_id = getattr(config, "criterion_benchmark", None)
+    if benchmark_id is not None:
+        if run_criterion:
+            run_criterion_benchmark(benchmark_id)
+        report_ffi_overhead(benchmark_id, rs_time, py_time, used_unit, used_mult)
+
 
 if __name__ == "__main__":
     parser = argparse.ArgumentParser(description="Python to Rust Workshop Benchmarker")
@@ -253,7 +346,13 @@ if __name__ == "__main__":
         help="Target duration for each benchmark in seconds (default: 1.0)",
     )
 
+    parser.add_argument(
+        "--criterion",
+        action="store_true",
+        help="Run the criterion benchmark named in workshop_config.py to measure the FFI overhead",
+    )
+
     args = parser.parse_args()
 
-    # Pass the parsed duration into main
-    main(benchmark_duration=args.bench_duration)
+    # Pass the parsed arguments into main
+    main(benchmark_duration=args.bench_duration, run_criterion=args.criterion)
@@ -0,0 +1,99 @@
+//! Benchmarks of the pure Rust functions, without any python overhead
+//!
+//! Run them with `cargo bench --bench fibonacci`, criterion writes the results to
+//! `target/criterion/<group>/<function>/<n>/new/estimates.json`.
+
+use std::hint::black_box;
+use std::time::Duration;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use fib_core::{
+    FIB_U128, MAX_U128_N, clear_pisano_cache, fibonacci, fibonacci_big, fibonacci_mod,
+    fibonacci_signed, pisano_period,
+};
+use num_bigint::BigInt;
+
+/// The straightforward loop the workshop starts out with
+fn fibonacci_iterative(n: u32) -> u128 {
+    let (mut a, mut b) = (0u128, 1u128);
+    for _ in 0..n {
+        (a, b) = (b, a.wrapping_add(b));
+    }
+    a
+}
+
+/// Every index of the u128 functions, so a regression at any of them shows up
+fn small(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fibonacci");
+    for n in 0..=MAX_U128_N {
+        group.bench_with_input(BenchmarkId::new("fast_doubling", n), &n, |b, &n| {
+            b.iter(|| fibonacci(black_box(n)))
+        });
+        group.bench_with_input(BenchmarkId::new("table", n), &n, |b, &n| {
+            b.iter(|| FIB_U128[black_box(n) as usize])
+        });
+        group.bench_with_input(BenchmarkId::new("iterative", n), &n, |b, &n| {
+            b.iter(|| fibonacci_iterative(black_box(n)))
+        });
+        // what `rust_lib.implementation` computes, before converting the result to a python int
+        group.bench_with_input(BenchmarkId::new("signed", n), &n, |b, &n| {
+            b.iter(|| fibonacci_signed(black_box(n.into())))
+        });
+    }
+    group.finish();
+}
+
+fn big(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fibonacci_big");
+    for n in [1_000, 10_000, 100_000] {
+        group.bench_with_input(BenchmarkId::new("fast_doubling", n), &n, |b, &n| {
+            b.iter(|| fibonacci_big(black_box(n)))
+        });
+    }
+    group.finish();
+}
+
+fn modular(c: &mut Criterion) {
+    let n = BigInt::from(10).pow(30);
+
+    let mut group = c.benchmark_group("fibonacci_mod");
+    for m in [1_000_000_007, u64::MAX.into(), u128::MAX] {
+        group.bench_with_input(BenchmarkId::new("fast_doubling", m), &m, |b, &m| {
+            b.iter(|| fibonacci_mod(black_box(&n), black_box(m)))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("pisano_period");
+    for m in [1_000, 1_000_000, 1_000_000_007] {
+        group.bench_with_input(BenchmarkId::new("uncached", m), &m, |b, &m| {
+            b.iter(|| {
+                clear_pisano_cache();
+                pisano_period(black_box(m))
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = sweep;
+    // these take nanoseconds, so short measurements keep the 748 benchmarks at a few minutes,
+    // bootstrapping the statistics and plotting would otherwise take far longer than measuring
+    config = Criterion::default()
+        .sample_size(10)
+        .warm_up_time(Duration::from_millis(50))
+        .measurement_time(Duration::from_millis(100))
+        .nresamples(1000)
+        .without_plots();
+    targets = small
+}
+criterion_group! {
+    name = benches;
+    // keep a full run of the larger benchmarks at around a minute
+    config = Criterion::default()
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(1));
+    targets = big, modular
+}
+criterion_main!(sweep, benches);
@@ -0,0 +1,43 @@
+#[cfg(feature = "python")]
+#[path = "build/stubs.rs"]
+mod stubs;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    #[cfg(feature = "capi")]
+    generate_header();
+    #[cfg(feature = "python")]
+    generate_stubs();
+}
+
+/// Writes the C header for the functions in `src/capi.rs` to `OUT_DIR`,
+/// `tests/capi.rs` compares it to the one in `include`
+#[cfg(feature = "capi")]
+fn generate_header() {
+    println!("cargo:rerun-if-changed=src/capi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    cbindgen::generate(&crate_dir)
+        .expect("Unable to generate the C header")
+        .write_to_file(format!("{out_dir}/fib.h"));
+}
+
+/// Writes the type stubs for the python module in `src/lib.rs` to `OUT_DIR`,
+/// `tests/stubs.rs` compares them to the ones in `python/rust_lib`
+#[cfg(feature = "python")]
+fn generate_stubs() {
+    println!("cargo:rerun-if-changed=build/stubs.rs");
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=src/exceptions.rs");
+
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    std::fs::write(
+        format!("{out_dir}/rust_lib.pyi"),
+        stubs::generate(&crate_dir),
+    )
+    .expect("Unable to write the type stubs");
+}
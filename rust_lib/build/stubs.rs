@@ -0,0 +1,522 @@
+//! Generates the type stubs `python/rust_lib/rust_lib.pyi` from the `#[pyfunction]`s and
+//! `#[pyclass]`es in `src/lib.rs` and the exception types created in `src/exceptions.rs`
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use syn::visit::Visit;
+use syn::{
+    Attribute, Expr, ExprCall, FnArg, GenericArgument, ImplItem, Item, Lit, Meta, Pat,
+    PathArguments, ReturnType, Signature, Type,
+};
+
+/// The values which can be fibonacci coded
+const CODING_VALUES: &str = "list[int] | tuple[int, ...] | npt.NDArray[np.integer]";
+
+/// Annotations which the Rust types can't tell, e.g. of `Bound<PyAny>`s,
+/// keyed by `function` for return values and by `function.parameter` for parameters
+const ANNOTATIONS: &[(&str, &str)] = &[
+    ("fibonacci_table", "memoryview"),
+    ("fibonacci_range", "list[int]"),
+    (
+        "fibonacci_batch",
+        "npt.NDArray[np.uint64] | npt.NDArray[np.object_]",
+    ),
+    ("zeckendorf", "list[int] | int"),
+    ("from_zeckendorf.representation", "int | Sequence[int]"),
+    ("fibonacci_encode.values", CODING_VALUES),
+    ("FibonacciEncoder.write.values", CODING_VALUES),
+];
+
+/// More precise signatures of functions whose return type depends on their arguments,
+/// they come before the generated signature as `@overload`s
+const OVERLOADS: &[(&str, &[&str])] = &[(
+    "zeckendorf",
+    &[
+        "(n: int, bitmask: Literal[False] = False) -> list[int]",
+        "(n: int, bitmask: Literal[True]) -> int",
+    ],
+)];
+
+/// The attributes `to_pyerr` sets on the exceptions
+const EXCEPTION_ATTRIBUTES: &[(&str, &[&str])] = &[
+    ("FibonacciOverflowError", &["n: int", "max_n: int"]),
+    ("NegativeIndexError", &["n: int"]),
+    ("ResourceLimitError", &["limit: int"]),
+];
+
+const HEADER: &str = r#"# Generated by `build.rs` from `src/lib.rs` and `src/exceptions.rs`, don't edit it by hand.
+# `tests/stubs.rs` checks that it is up to date and matches the compiled module,
+# `UPDATE_GENERATED=1 cargo test --test stubs` updates it.
+
+import os
+from collections.abc import Sequence
+from typing import Literal, overload
+
+import numpy as np
+import numpy.typing as npt
+from _typeshed import ReadableBuffer
+
+__all__: list[str]
+"#;
+
+/// The stubs for the crate in `crate_dir`
+pub fn generate(crate_dir: &str) -> String {
+    let lib = parse(&format!("{crate_dir}/src/lib.rs"));
+    let module = lib
+        .items
+        .iter()
+        .find_map(|item| match item {
+            Item::Mod(module) if module.ident == "rust_lib" => module.content.as_ref(),
+            _ => None,
+        })
+        .map(|(_, items)| items)
+        .expect("src/lib.rs has no module `rust_lib`");
+
+    let mut stubs = Stubs {
+        out: HEADER.to_string(),
+        classes: module
+            .iter()
+            .filter_map(|item| match item {
+                Item::Struct(class) => Some((class.ident.to_string(), pyclass_name(class)?)),
+                _ => None,
+            })
+            .collect(),
+    };
+
+    for item in module {
+        match item {
+            Item::Fn(function) if has_attribute(&function.attrs, "pyfunction") => {
+                stubs.separate();
+                stubs.function(&function.attrs, &function.sig, None, "");
+            }
+            Item::Struct(class) if has_attribute(&class.attrs, "pyclass") => {
+                let methods = module.iter().filter_map(|item| match item {
+                    Item::Impl(methods)
+                        if has_attribute(&methods.attrs, "pymethods")
+                            && matches!(&*methods.self_ty, Type::Path(ty) if ty.path.is_ident(&class.ident)) =>
+                    {
+                        Some(&methods.items)
+                    }
+                    _ => None,
+                });
+                stubs.class(&class.attrs, &class.ident.to_string(), methods.flatten());
+            }
+            _ => {}
+        }
+    }
+
+    let mut exceptions = Exceptions::default();
+    exceptions.visit_file(&parse(&format!("{crate_dir}/src/exceptions.rs")));
+    for (name, doc, bases) in exceptions.0 {
+        stubs.separate();
+        writeln!(stubs.out, "class {name}({}):", bases.join(", ")).unwrap();
+        docstring(&mut stubs.out, &doc, "    ");
+        let attributes = EXCEPTION_ATTRIBUTES
+            .iter()
+            .find(|(exception, _)| *exception == name);
+        if let Some((_, attributes)) = attributes {
+            stubs.out.push('\n');
+            for attribute in *attributes {
+                writeln!(stubs.out, "    {attribute}").unwrap();
+            }
+        }
+    }
+
+    stubs.out
+}
+
+fn parse(path: &str) -> syn::File {
+    let source = std::fs::read_to_string(path).unwrap_or_else(|error| panic!("{path}: {error}"));
+    syn::parse_file(&source).unwrap_or_else(|error| panic!("{path}: {error}"))
+}
+
+struct Stubs {
+    out: String,
+    /// the python names of the `#[pyclass]`es by their Rust names
+    classes: HashMap<String, String>,
+}
+
+impl Stubs {
+    /// Ends the previous item with a blank line, unless its docstring already did
+    fn separate(&mut self) {
+        if !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn class<'a>(
+        &mut self,
+        attrs: &[Attribute],
+        rust_name: &str,
+        methods: impl Iterator<Item = &'a ImplItem>,
+    ) {
+        let name = &self.classes[rust_name].clone();
+        self.separate();
+        writeln!(self.out, "class {name}:").unwrap();
+        docstring(&mut self.out, &doc(attrs), "    ");
+        self.out.push('\n');
+
+        for method in methods {
+            if let ImplItem::Fn(method) = method {
+                self.function(&method.attrs, &method.sig, Some(name), "    ");
+            }
+        }
+    }
+
+    /// Writes a function or, if `class` is set, a method
+    fn function(
+        &mut self,
+        attrs: &[Attribute],
+        signature: &Signature,
+        class: Option<&str>,
+        indent: &str,
+    ) {
+        let options = Pyo3Options::from(attrs);
+        let constructor = has_attribute(attrs, "new");
+        let getter = has_attribute(attrs, "getter");
+        let name = match (constructor, options.name) {
+            (true, _) => "__new__".to_string(),
+            (false, Some(name)) => name,
+            (false, None) => signature.ident.to_string(),
+        };
+        let qualified = match class {
+            Some(class) => format!("{class}.{name}"),
+            None => name.clone(),
+        };
+
+        let mut parameters = Vec::new();
+        if constructor {
+            parameters.push("cls".to_string());
+        }
+        for argument in &signature.inputs {
+            let (ident, ty) = match argument {
+                FnArg::Receiver(_) => {
+                    parameters.push("self".to_string());
+                    continue;
+                }
+                FnArg::Typed(argument) => match &*argument.pat {
+                    Pat::Ident(ident) => (ident.ident.to_string(), &*argument.ty),
+                    _ => panic!("`{qualified}` has a pattern as a parameter"),
+                },
+            };
+            if ident == "slf" {
+                parameters.push("self".to_string());
+                continue;
+            }
+            if last_ident(ty).is_some_and(|ty| ty == "Python") {
+                continue;
+            }
+
+            let annotation = self.annotation(&format!("{qualified}.{ident}"), ty, true, class);
+            let default = options
+                .defaults
+                .iter()
+                .find(|(parameter, _)| *parameter == ident)
+                .and_then(|(_, default)| default.as_deref());
+            parameters.push(match default {
+                Some(default) => format!("{ident}: {annotation} = {default}"),
+                None => format!("{ident}: {annotation}"),
+            });
+        }
+
+        let returns = match (&signature.output, constructor) {
+            (_, true) => class.expect("constructors are methods").to_string(),
+            (ReturnType::Default, false) => "None".to_string(),
+            // returning None from `__next__` stops the iteration
+            (ReturnType::Type(_, ty), false) if name == "__next__" => {
+                let item = generic_arguments(ty).into_iter().next();
+                self.annotation(&qualified, item.unwrap_or(ty), false, class)
+            }
+            (ReturnType::Type(_, ty), false) => self.annotation(&qualified, ty, false, class),
+        };
+
+        let overloads = OVERLOADS
+            .iter()
+            .find(|(function, _)| *function == qualified);
+        let doc = doc(attrs);
+        let mut docstring_written = false;
+        for overload in overloads.map_or(&[][..], |(_, overloads)| overloads) {
+            write!(self.out, "{indent}@overload\n{indent}def {name}{overload}").unwrap();
+            self.body(&doc, indent, &mut docstring_written);
+        }
+        if overloads.is_some() {
+            writeln!(self.out, "{indent}@overload").unwrap();
+        }
+        if getter {
+            writeln!(self.out, "{indent}@property").unwrap();
+        }
+        write!(
+            self.out,
+            "{indent}def {name}({}) -> {returns}",
+            parameters.join(", ")
+        )
+        .unwrap();
+        self.body(&doc, indent, &mut docstring_written);
+    }
+
+    /// Writes the docstring as the body of the first overload and `...` for all others
+    fn body(&mut self, doc: &str, indent: &str, docstring_written: &mut bool) {
+        if doc.is_empty() || *docstring_written {
+            self.out.push_str(": ...\n");
+        } else {
+            self.out.push_str(":\n");
+            docstring(&mut self.out, doc, &format!("{indent}    "));
+            self.out.push('\n');
+            *docstring_written = true;
+        }
+    }
+
+    fn annotation(&self, key: &str, ty: &Type, parameter: bool, class: Option<&str>) -> String {
+        ANNOTATIONS
+            .iter()
+            .find(|(annotated, _)| *annotated == key)
+            .map(|(_, annotation)| annotation.to_string())
+            .or_else(|| self.python_type(ty, parameter, class))
+            .unwrap_or_else(|| {
+                panic!("`{key}` has no python type, add it to `ANNOTATIONS` in build/stubs.rs")
+            })
+    }
+
+    /// The python type a Rust type is converted from as a parameter or into as a return value
+    fn python_type(&self, ty: &Type, parameter: bool, class: Option<&str>) -> Option<String> {
+        let ty = match ty {
+            Type::Reference(reference) => &*reference.elem,
+            Type::Tuple(tuple) if tuple.elems.is_empty() => return Some("None".to_string()),
+            ty => ty,
+        };
+        let arguments = generic_arguments(ty);
+        let argument = |i: usize| self.python_type(arguments.get(i)?, parameter, class);
+
+        Some(match last_ident(ty)?.as_str() {
+            "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64"
+            | "i128" | "isize" | "BigInt" | "BigUint" | "PyInt" | "PythonInt" => "int".to_string(),
+            "bool" => "bool".to_string(),
+            "String" => "str".to_string(),
+            "PathBuf" => "str | os.PathLike[str]".to_string(),
+            "PyBytes" => "bytes".to_string(),
+            "PyBuffer" => "ReadableBuffer".to_string(),
+            "PyArrayLike1" => "npt.ArrayLike".to_string(),
+            "PyArray1" => format!("npt.NDArray[{}]", numpy_type(arguments.first()?)?),
+            "Option" => format!("{} | None", argument(0)?),
+            // any sequence is extracted into a `Vec`, but they are returned as lists
+            "Vec" if parameter => format!("Sequence[{}]", argument(0)?),
+            "Vec" => format!("list[{}]", argument(0)?),
+            "PyResult" | "Bound" | "PyRef" | "PyRefMut" => argument(0)?,
+            "Self" => class?.to_string(),
+            name => self.classes.get(name)?.clone(),
+        })
+    }
+}
+
+/// The exception types, with their docstrings and bases, from the calls of `new_exception`
+#[derive(Default)]
+struct Exceptions(Vec<(String, String, Vec<String>)>);
+
+impl<'ast> Visit<'ast> for Exceptions {
+    fn visit_expr_call(&mut self, call: &'ast ExprCall) {
+        syn::visit::visit_expr_call(self, call);
+        if !matches!(&*call.func, Expr::Path(function) if function.path.is_ident("new_exception")) {
+            return;
+        }
+
+        let string = |expr: &Expr| match expr {
+            Expr::Lit(literal) => match &literal.lit {
+                Lit::Str(string) => Some(string.value()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let arguments = call.args.iter().collect::<Vec<_>>();
+        let (Some(name), Some(doc), Expr::Reference(bases)) =
+            (string(arguments[1]), string(arguments[2]), arguments[3])
+        else {
+            panic!("src/exceptions.rs calls `new_exception` without literal names, docs or bases");
+        };
+        let Expr::Array(bases) = &*bases.expr else {
+            panic!("the bases of `{name}` are not an array");
+        };
+
+        let bases = bases
+            .elems
+            .iter()
+            .map(|base| match base {
+                // `py.get_type::<PyOverflowError>()` is the builtin `OverflowError`
+                Expr::MethodCall(call) if call.method == "get_type" => {
+                    let ty = call
+                        .turbofish
+                        .as_ref()
+                        .and_then(|turbofish| turbofish.args.first());
+                    match ty {
+                        Some(GenericArgument::Type(ty)) => last_ident(ty)
+                            .and_then(|ty| ty.strip_prefix("Py").map(str::to_string))
+                            .expect("builtin exceptions are named `PyName`"),
+                        _ => panic!("`get_type` without a type"),
+                    }
+                }
+                // everything else is the base class of them all, which is created first
+                _ => self
+                    .0
+                    .first()
+                    .expect("the base class comes first")
+                    .0
+                    .clone(),
+            })
+            .collect();
+        self.0.push((name, doc, bases));
+    }
+}
+
+/// The docstring pyo3 generates from the doc comments
+fn doc(attrs: &[Attribute]) -> String {
+    let lines = attrs.iter().filter_map(|attr| match &attr.meta {
+        Meta::NameValue(doc) if doc.path.is_ident("doc") => match &doc.value {
+            Expr::Lit(literal) => match &literal.lit {
+                Lit::Str(line) => Some(line.value()),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    });
+
+    lines
+        .map(|line| line.strip_prefix(' ').map(str::to_string).unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn docstring(out: &mut String, doc: &str, indent: &str) {
+    let doc = doc.replace('\\', r"\\").replace(r#"""""#, r#"\"\"\""#);
+    let mut lines = doc.lines();
+    write!(out, "{indent}\"\"\"{}", lines.next().unwrap_or_default()).unwrap();
+    for line in lines {
+        match line {
+            "" => out.push('\n'),
+            line => write!(out, "\n{indent}{line}").unwrap(),
+        }
+    }
+    out.push_str("\"\"\"\n");
+}
+
+/// The `name` and the defaults of the `signature` in `#[pyo3(...)]`
+#[derive(Default)]
+struct Pyo3Options {
+    name: Option<String>,
+    /// the python default of every parameter in the signature, `...` if it is not a literal
+    defaults: Vec<(String, Option<String>)>,
+}
+
+impl From<&[Attribute]> for Pyo3Options {
+    fn from(attrs: &[Attribute]) -> Pyo3Options {
+        let mut options = Pyo3Options::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("pyo3")) {
+            attr.parse_nested_meta(|meta| {
+                let value = meta.value()?;
+                if meta.path.is_ident("name") {
+                    options.name = Some(value.parse::<syn::LitStr>()?.value());
+                } else if meta.path.is_ident("signature") {
+                    options.defaults = match value.parse::<Expr>()? {
+                        Expr::Tuple(tuple) => tuple.elems.iter().map(parameter_default).collect(),
+                        Expr::Paren(parameter) => vec![parameter_default(&parameter.expr)],
+                        _ => Vec::new(),
+                    };
+                } else {
+                    value.parse::<Expr>()?;
+                }
+                Ok(())
+            })
+            .expect("unsupported #[pyo3] attribute");
+        }
+        options
+    }
+}
+
+fn parameter_default(parameter: &Expr) -> (String, Option<String>) {
+    let name = |expr: &Expr| match expr {
+        Expr::Path(path) => path.path.get_ident().map(ToString::to_string),
+        _ => None,
+    };
+
+    match parameter {
+        Expr::Assign(assign) => {
+            let default = match &*assign.right {
+                Expr::Lit(literal) => match &literal.lit {
+                    Lit::Bool(value) if value.value => "True".to_string(),
+                    Lit::Bool(_) => "False".to_string(),
+                    Lit::Int(value) => value.base10_digits().to_string(),
+                    _ => "...".to_string(),
+                },
+                Expr::Path(path) if path.path.is_ident("None") => "None".to_string(),
+                _ => "...".to_string(),
+            };
+            (
+                name(&assign.left).expect("parameter names are identifiers"),
+                Some(default),
+            )
+        }
+        parameter => (
+            name(parameter).expect("parameter names are identifiers"),
+            None,
+        ),
+    }
+}
+
+fn pyclass_name(class: &syn::ItemStruct) -> Option<String> {
+    let attr = class
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("pyclass"))?;
+    let mut name = class.ident.to_string();
+    // `#[pyclass]` without any options has nothing to parse
+    let _ = attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("name") {
+            name = meta.value()?.parse::<syn::LitStr>()?.value();
+        } else if meta.input.peek(syn::Token![=]) {
+            meta.value()?.parse::<Expr>()?;
+        }
+        Ok(())
+    });
+    Some(name)
+}
+
+fn has_attribute(attrs: &[Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| attr.path().is_ident(name))
+}
+
+fn last_ident(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(path) => Some(path.path.segments.last()?.ident.to_string()),
+        _ => None,
+    }
+}
+
+/// The type arguments of e.g. `Option<T>`, without lifetimes
+fn generic_arguments(ty: &Type) -> Vec<&Type> {
+    let Type::Path(path) = ty else {
+        return Vec::new();
+    };
+    match path.path.segments.last().map(|segment| &segment.arguments) {
+        Some(PathArguments::AngleBracketed(arguments)) => arguments
+            .args
+            .iter()
+            .filter_map(|argument| match argument {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The NumPy scalar type of the elements of an array
+fn numpy_type(ty: &Type) -> Option<String> {
+    let ty = last_ident(ty)?;
+    Some(match ty.as_str() {
+        "bool" => "np.bool_".to_string(),
+        ty if ty.starts_with('u') => format!("np.uint{}", &ty[1..]),
+        ty if ty.starts_with('i') => format!("np.int{}", &ty[1..]),
+        ty if ty.starts_with('f') => format!("np.float{}", &ty[1..]),
+        _ => return None,
+    })
+}
@@ -0,0 +1,296 @@
+//! `fib`, a command line tool to spot check fibonacci numbers without starting python
+
+use std::fmt;
+use std::io::{self, BufWriter, Write};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use fib_core::{
+    FibError, FibonacciIter, fibonacci_index, fibonacci_mod, fibonacci_signed, pisano_period,
+    zeckendorf,
+};
+use num_bigint::{BigInt, BigUint, Sign};
+use num_integer::Integer;
+
+#[derive(Parser)]
+#[command(
+    name = "fib",
+    version,
+    about = "Computes fibonacci numbers and related values"
+)]
+struct Cli {
+    /// How the results are printed
+    #[arg(short, long, value_enum, default_value_t = Format::Decimal, global = true)]
+    format: Format,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Decimal,
+    Hex,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Prints F(n), n may be negative
+    Nth {
+        #[arg(allow_negative_numbers = true)]
+        n: i64,
+    },
+    /// Prints F(start), ..., F(stop - 1), one per line
+    Range { start: u32, stop: u32 },
+    /// Prints F(n) mod m
+    Mod {
+        #[arg(allow_negative_numbers = true)]
+        n: BigInt,
+        m: u128,
+    },
+    /// Prints the pisano period π(m), after which the fibonacci numbers mod m repeat
+    Pisano { m: u64 },
+    /// Prints the index of x if it is a fibonacci number, and fails otherwise
+    IsFib {
+        #[arg(allow_negative_numbers = true)]
+        x: BigInt,
+    },
+    /// Prints the Zeckendorf representation of n, as the indices of its fibonacci numbers
+    Zeckendorf { n: BigUint },
+}
+
+/// Everything that can make the tool fail
+enum Error {
+    Fib(FibError),
+    Io(io::Error),
+}
+
+impl From<FibError> for Error {
+    fn from(error: FibError) -> Error {
+        Error::Fib(error)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Error {
+        Error::Io(error)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Fib(error) => error.fmt(f),
+            Error::Io(error) => error.fmt(f),
+        }
+    }
+}
+
+/// Writes numbers in the requested [`Format`]
+struct Printer<W: Write> {
+    out: W,
+    format: Format,
+}
+
+impl<W: Write> Printer<W> {
+    fn number(&mut self, value: impl Into<BigInt>) -> io::Result<()> {
+        let value = value.into();
+        match self.format {
+            // JSON numbers have arbitrary precision, python's `json` module reads them exactly
+            Format::Decimal | Format::Json => {
+                if value.sign() == Sign::Minus {
+                    write!(self.out, "-")?;
+                }
+
+                let magnitude = value.magnitude();
+                // powers[i] = 10^(19 * 2^i), until powers.last()² is larger than the value
+                let mut powers = vec![BigUint::from(DECIMAL_CHUNK)];
+                loop {
+                    let square = powers[powers.len() - 1].pow(2);
+                    if square > *magnitude {
+                        break;
+                    }
+                    powers.push(square);
+                }
+                write_decimal(&mut self.out, magnitude, &powers, false)
+            }
+            Format::Hex => {
+                if value.sign() == Sign::Minus {
+                    write!(self.out, "-")?;
+                }
+
+                // write the limbs one by one instead of building the whole string
+                let limbs = value.magnitude().to_u64_digits();
+                let Some((last, rest)) = limbs.split_last() else {
+                    return write!(self.out, "0x0");
+                };
+                write!(self.out, "{last:#x}")?;
+                for limb in rest.iter().rev() {
+                    write!(self.out, "{limb:016x}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Writes a sequence of numbers, as a JSON array or one per line
+    fn numbers<T: Into<BigInt>>(&mut self, values: impl IntoIterator<Item = T>) -> io::Result<()> {
+        let json = self.format == Format::Json;
+        if json {
+            write!(self.out, "[")?;
+        }
+
+        for (i, value) in values.into_iter().enumerate() {
+            if i > 0 {
+                write!(self.out, "{}", if json { "," } else { "\n" })?;
+            }
+            self.number(value)?;
+        }
+
+        if json {
+            write!(self.out, "]")?;
+        }
+        Ok(())
+    }
+}
+
+/// 10^19, the largest power of ten which fits into a u64
+const DECIMAL_CHUNK: u64 = 10_000_000_000_000_000_000;
+
+/// Writes `value` in decimal without building the whole string, by splitting it at powers of ten
+///
+/// `value` has to be smaller than `powers.last()²`, or than [`DECIMAL_CHUNK`] if `powers` is empty.
+/// If `padded` is set, it is written with leading zeros to as many digits as that bound has.
+fn write_decimal(
+    out: &mut impl Write,
+    value: &BigUint,
+    powers: &[BigUint],
+    padded: bool,
+) -> io::Result<()> {
+    let Some((power, lower)) = powers.split_last() else {
+        let value = u64::try_from(value).expect("smaller than 10^19");
+        return match padded {
+            true => write!(out, "{value:019}"),
+            false => write!(out, "{value}"),
+        };
+    };
+
+    if !padded && value < power {
+        return write_decimal(out, value, lower, false);
+    }
+
+    let (high, low) = value.div_rem(power);
+    write_decimal(out, &high, lower, padded)?;
+    write_decimal(out, &low, lower, true)
+}
+
+fn run<W: Write>(command: Command, printer: &mut Printer<W>) -> Result<ExitCode, Error> {
+    match command {
+        Command::Nth { n } => printer.number(fibonacci_signed(n)?)?,
+        Command::Range { start, stop } => printer.numbers(
+            FibonacciIter::new(start, Some(stop), 1).map(|f| BigInt::from(BigUint::from(f))),
+        )?,
+        Command::Mod { n, m } => printer.number(fibonacci_mod(&n, m)?)?,
+        Command::Pisano { m } => printer.number(pisano_period(m)?)?,
+        Command::IsFib { x } => {
+            let index = fibonacci_index(&x)?;
+            match (printer.format, index) {
+                (Format::Json, Some(index)) => {
+                    write!(printer.out, r#"{{"is_fibonacci":true,"index":{index}}}"#)?
+                }
+                (Format::Json, None) => {
+                    write!(printer.out, r#"{{"is_fibonacci":false,"index":null}}"#)?
+                }
+                (_, Some(index)) => printer.number(index)?,
+                (_, None) => write!(printer.out, "not a fibonacci number")?,
+            }
+
+            if index.is_none() {
+                writeln!(printer.out)?;
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Zeckendorf { n } => printer.numbers(zeckendorf(&n)?)?,
+    }
+
+    writeln!(printer.out)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let mut printer = Printer {
+        out: BufWriter::new(io::stdout().lock()),
+        format: cli.format,
+    };
+
+    let result = run(cli.command, &mut printer).and_then(|code| {
+        printer.out.flush()?;
+        Ok(code)
+    });
+
+    match result {
+        Ok(code) => code,
+        // e.g. piping a long range into `head`
+        Err(Error::Io(error)) if error.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("fib: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn print(format: Format, value: impl Into<BigInt>) -> String {
+        let mut printer = Printer {
+            out: Vec::new(),
+            format,
+        };
+        printer.number(value).unwrap();
+        String::from_utf8(printer.out).unwrap()
+    }
+
+    #[test]
+    fn decimal_output_matches_display() {
+        let chunk = BigInt::from(DECIMAL_CHUNK);
+        let mut values = vec![
+            BigInt::ZERO,
+            BigInt::from(7),
+            &chunk - 1,
+            chunk.clone(),
+            &chunk + 1,
+            chunk.pow(2),
+            chunk.pow(2) - 1,
+            chunk.pow(4) + 5,
+        ];
+        values.extend(
+            [10u32, 186, 187, 1000, 10_000].map(|n| BigInt::from(fib_core::fibonacci_big(n))),
+        );
+        values.extend(values.clone().into_iter().map(|value| -value));
+
+        for value in values {
+            assert_eq!(print(Format::Decimal, value.clone()), value.to_string());
+            assert_eq!(print(Format::Json, value.clone()), value.to_string());
+        }
+    }
+
+    #[test]
+    fn hex_output_matches_display() {
+        for value in [
+            BigInt::ZERO,
+            BigInt::from(255),
+            BigInt::from(-255),
+            BigInt::from(1u128 << 64),
+        ] {
+            let expected = match value.sign() {
+                Sign::Minus => format!("-{:#x}", value.magnitude()),
+                _ => format!("{:#x}", value.magnitude()),
+            };
+            assert_eq!(print(Format::Hex, value), expected);
+        }
+    }
+}
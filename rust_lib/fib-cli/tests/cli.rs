@@ -0,0 +1,97 @@
+//! Runs the `fib` binary and checks its output and exit codes
+
+use std::process::{Command, Output};
+
+fn fib(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_fib"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Runs `fib` and returns its output, which has to be successful
+fn stdout(args: &[&str]) -> String {
+    let output = fib(args);
+    assert!(output.status.success(), "{args:?}: {output:?}");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn nth() {
+    assert_eq!(stdout(&["nth", "10"]), "55\n");
+    assert_eq!(stdout(&["nth", "-10"]), "-55\n");
+    assert_eq!(
+        stdout(&["nth", "100", "--format", "hex"]),
+        "0x1333db76a7c594bfc3\n"
+    );
+    assert_eq!(stdout(&["--format", "json", "nth", "-6"]), "-8\n");
+    assert_eq!(
+        stdout(&["nth", "200"]),
+        "280571172992510140037611932413038677189525\n"
+    );
+}
+
+#[test]
+fn range() {
+    assert_eq!(stdout(&["range", "0", "8"]), "0\n1\n1\n2\n3\n5\n8\n13\n");
+    assert_eq!(stdout(&["range", "5", "8", "-f", "json"]), "[5,8,13]\n");
+    assert_eq!(stdout(&["range", "10", "12", "-f", "hex"]), "0x37\n0x59\n");
+    assert_eq!(stdout(&["range", "3", "3"]), "\n");
+}
+
+#[test]
+fn modular_and_pisano() {
+    assert_eq!(stdout(&["mod", "100", "1000"]), "75\n");
+    assert_eq!(stdout(&["mod", "-1", "10"]), "1\n");
+    assert_eq!(stdout(&["pisano", "10"]), "60\n");
+}
+
+#[test]
+fn is_fib() {
+    assert_eq!(stdout(&["is-fib", "144"]), "12\n");
+    assert_eq!(
+        stdout(&["is-fib", "-8", "--format", "json"]),
+        "{\"is_fibonacci\":true,\"index\":-6}\n"
+    );
+
+    let output = fib(&["is-fib", "100"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(output.stdout, b"not a fibonacci number\n");
+
+    let output = fib(&["is-fib", "100", "-f", "json"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(output.stdout, b"{\"is_fibonacci\":false,\"index\":null}\n");
+}
+
+#[test]
+fn zeckendorf() {
+    assert_eq!(stdout(&["zeckendorf", "100"]), "11\n6\n4\n");
+    assert_eq!(stdout(&["zeckendorf", "100", "-f", "json"]), "[11,6,4]\n");
+    assert_eq!(stdout(&["zeckendorf", "0", "-f", "json"]), "[]\n");
+}
+
+#[test]
+fn errors_fail_with_a_message() {
+    for (args, message) in [
+        (
+            &["nth", "4294967296"][..],
+            "fib: Indices larger than 4294967295 are not supported\n",
+        ),
+        (&["mod", "10", "0"], "fib: The modulus must not be zero\n"),
+        (&["pisano", "0"], "fib: The modulus must not be zero\n"),
+    ] {
+        let output = fib(args);
+        assert_eq!(output.status.code(), Some(1), "{args:?}");
+        assert!(output.stdout.is_empty(), "{args:?}");
+        assert_eq!(
+            String::from_utf8(output.stderr).unwrap(),
+            message,
+            "{args:?}"
+        );
+    }
+
+    // invalid arguments are reported by clap
+    let output = fib(&["zeckendorf", "-1"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(!output.stderr.is_empty());
+}
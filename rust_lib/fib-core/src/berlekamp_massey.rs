@@ -0,0 +1,436 @@
+//! The Berlekamp-Massey algorithm, which finds the shortest linear recurrence generating a sequence
+
+use num_bigint::BigInt;
+use num_traits::{One, Zero};
+
+use crate::error::FibError;
+use crate::factor::{is_prime, mul_mod, pow_mod};
+use crate::modular::reduce;
+use crate::recurrence::LinearRecurrence;
+
+/// The arithmetic Berlekamp-Massey needs: a prime field
+trait Field {
+    fn zero(&self) -> u64;
+    fn one(&self) -> u64;
+    fn add(&self, a: u64, b: u64) -> u64;
+    fn sub(&self, a: u64, b: u64) -> u64;
+    fn mul(&self, a: u64, b: u64) -> u64;
+    fn inverse(&self, a: u64) -> u64;
+}
+
+/// The integers modulo a prime
+struct PrimeField(u64);
+
+impl Field for PrimeField {
+    fn zero(&self) -> u64 {
+        0
+    }
+    fn one(&self) -> u64 {
+        1
+    }
+    fn add(&self, a: u64, b: u64) -> u64 {
+        ((a as u128 + b as u128) % self.0 as u128) as u64
+    }
+    fn sub(&self, a: u64, b: u64) -> u64 {
+        self.add(a, self.0 - b)
+    }
+    fn mul(&self, a: u64, b: u64) -> u64 {
+        mul_mod(a, b, self.0)
+    }
+    fn inverse(&self, a: u64) -> u64 {
+        // a^(p - 2) is the inverse of a by fermat's little theorem
+        pow_mod(a, self.0 - 2, self.0)
+    }
+}
+
+/// The integers modulo an odd prime below 2^62 in Montgomery form: x is stored as x * 2^64 mod p,
+/// so that multiplications don't need a slow 128 bit division
+struct MontgomeryField {
+    p: u64,
+    /// -p^(-1) mod 2^64
+    negated_inverse: u64,
+    /// 2^128 mod p, to convert into Montgomery form
+    r2: u64,
+}
+
+impl MontgomeryField {
+    fn new(p: u64) -> MontgomeryField {
+        // newton's iteration doubles the correct low bits of p^(-1) mod 2^64 each step
+        let mut inverse = p;
+        for _ in 0..5 {
+            inverse = inverse.wrapping_mul(2u64.wrapping_sub(p.wrapping_mul(inverse)));
+        }
+        let r = (1u128 << 64) % p as u128;
+
+        MontgomeryField {
+            p,
+            negated_inverse: inverse.wrapping_neg(),
+            r2: (r * r % p as u128) as u64,
+        }
+    }
+
+    /// Computes x * 2^(-64) mod p for x < p * 2^64
+    fn redc(&self, x: u128) -> u64 {
+        let m = (x as u64).wrapping_mul(self.negated_inverse);
+        let reduced = ((x + m as u128 * self.p as u128) >> 64) as u64;
+        if reduced >= self.p {
+            reduced - self.p
+        } else {
+            reduced
+        }
+    }
+
+    /// The connection polynomial of `terms`, which are reduced modulo p
+    fn connection_polynomial(&self, terms: &[BigInt]) -> Vec<u64> {
+        let terms = terms
+            .iter()
+            .map(|x| self.mul(reduce(x, self.p.into()) as u64, self.r2))
+            .collect::<Vec<_>>();
+
+        connection_polynomial(self, &terms)
+            .into_iter()
+            .map(|c| self.redc(c.into()))
+            .collect()
+    }
+}
+
+impl Field for MontgomeryField {
+    fn zero(&self) -> u64 {
+        0
+    }
+    fn one(&self) -> u64 {
+        self.redc(self.r2.into())
+    }
+    fn add(&self, a: u64, b: u64) -> u64 {
+        let sum = a + b;
+        if sum >= self.p { sum - self.p } else { sum }
+    }
+    fn sub(&self, a: u64, b: u64) -> u64 {
+        if a >= b { a - b } else { a + self.p - b }
+    }
+    fn mul(&self, a: u64, b: u64) -> u64 {
+        self.redc(a as u128 * b as u128)
+    }
+    fn inverse(&self, a: u64) -> u64 {
+        // a^(p - 2) is the inverse of a by fermat's little theorem
+        let (mut base, mut exponent) = (a, self.p - 2);
+        let mut result = self.one();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = self.mul(result, base);
+            }
+            base = self.mul(base, base);
+            exponent >>= 1;
+        }
+        result
+    }
+}
+
+/// Finds the connection polynomial `1 + C_1 * x + ... + C_L * x^L` of the shortest
+/// recurrence a_n = -C_1 * a_(n - 1) - ... - C_L * a_(n - L) which generates `terms`
+fn connection_polynomial(field: &impl Field, terms: &[u64]) -> Vec<u64> {
+    let mut current = vec![field.one()];
+    let mut previous = vec![field.one()];
+    let mut length = 0;
+    // the discrepancy of `previous` and how many terms ago it was replaced
+    let mut previous_discrepancy = field.one();
+    let mut shift = 1;
+
+    for n in 0..terms.len() {
+        // how far the current recurrence is off for the nth term
+        let discrepancy = (1..=length).fold(terms[n], |sum, i| {
+            field.add(sum, field.mul(current[i], terms[n - i]))
+        });
+
+        if discrepancy == 0 {
+            shift += 1;
+            continue;
+        }
+
+        // current -= discrepancy / previous_discrepancy * x^shift * previous
+        let scale = field.mul(discrepancy, field.inverse(previous_discrepancy));
+        let mut corrected = current.clone();
+        corrected.resize(corrected.len().max(previous.len() + shift), field.zero());
+        for (i, &coefficient) in previous.iter().enumerate() {
+            corrected[i + shift] = field.sub(corrected[i + shift], field.mul(scale, coefficient));
+        }
+
+        if 2 * length <= n {
+            length = n + 1 - length;
+            previous = std::mem::replace(&mut current, corrected);
+            previous_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            current = corrected;
+            shift += 1;
+        }
+    }
+
+    current.resize(length + 1, field.zero());
+    current
+}
+
+/// Builds the recurrence from the negated coefficients of its connection polynomial, the sequence
+/// of all zeros is turned into the recurrence a_n = 0 * a_(n - 1), as every recurrence needs a coefficient
+fn recurrence_from(coefficients: Vec<BigInt>, terms: &[BigInt]) -> LinearRecurrence {
+    if coefficients.is_empty() {
+        return LinearRecurrence::new(vec![BigInt::ZERO], vec![BigInt::ZERO]);
+    }
+
+    let initial = terms[..coefficients.len()].to_vec();
+    LinearRecurrence::new(coefficients, initial)
+}
+
+/// Checks that the connection polynomial generates all of `terms`
+fn generates(connection: &[BigInt], terms: &[BigInt]) -> bool {
+    let order = connection.len() - 1;
+    (order..terms.len()).all(|n| {
+        (1..=order)
+            .fold(terms[n].clone(), |sum, i| {
+                sum + &connection[i] * &terms[n - i]
+            })
+            .is_zero()
+    })
+}
+
+/// The number of bits of the largest integer coefficients a recurrence of order `order` can have
+///
+/// The coefficients solve a linear system of Hankel matrices, so by Cramer's rule they are
+/// quotients of determinants of `order x order` matrices of terms, and the numerators are
+/// bounded by Hadamard's inequality: the product of the row lengths.
+fn coefficient_bits(order: usize, term_bits: u64) -> u64 {
+    let row_bits = term_bits + order.checked_ilog2().unwrap_or(0) as u64 / 2 + 1;
+    order as u64 * row_bits + 1
+}
+
+/// The CRT combination of the connection polynomials modulo several primes
+struct Combined {
+    /// the product of the primes
+    modulus: BigInt,
+    primes: u64,
+    /// the coefficients in the symmetric range `-modulus / 2..=modulus / 2`
+    coefficients: Vec<BigInt>,
+}
+
+impl Combined {
+    fn new(p: u64, connection: &[u64]) -> Combined {
+        let mut combined = Combined {
+            modulus: BigInt::one(),
+            primes: 0,
+            coefficients: vec![BigInt::ZERO; connection.len()],
+        };
+        combined.add(p, connection);
+        combined
+    }
+
+    /// Adds the connection polynomial modulo `p`, returns whether that changed any coefficient
+    fn add(&mut self, p: u64, connection: &[u64]) -> bool {
+        let field = PrimeField(p);
+        let inverse = field.inverse(reduce(&self.modulus, p.into()) as u64);
+        let half = &self.modulus * p / 2;
+
+        let mut changed = false;
+        for (coefficient, &residue) in self.coefficients.iter_mut().zip(connection) {
+            // the coefficient + modulus * t which is `residue` modulo p
+            let current = reduce(coefficient, p.into()) as u64;
+            let t = field.mul(field.sub(residue, current), inverse);
+            if t == 0 {
+                continue;
+            }
+
+            changed = true;
+            *coefficient += &self.modulus * t;
+            if *coefficient > half {
+                *coefficient -= &self.modulus * p;
+            }
+        }
+
+        self.modulus *= p;
+        self.primes += 1;
+        changed
+    }
+}
+
+/// A pure Rust function to find the shortest linear recurrence with integer
+/// coefficients which generates `terms`, or None if it has non-integer coefficients
+///
+/// The recurrence is only unique if `terms` contains at least twice as many terms as its order.
+///
+/// Rational arithmetic blows up quickly, so the recurrence is found modulo 62 bit primes, which
+/// are combined with the chinese remainder theorem until the coefficients stop changing and the
+/// recurrence generates `terms`. Only integer coefficients are of interest, so they are read off
+/// the symmetric residues instead of a rational reconstruction. Integer coefficients can't be larger than `coefficient_bits`,
+/// so once the product of the primes is larger than that, changing coefficients mean that
+/// they are not integers. The result is checked against `terms` either way.
+pub fn berlekamp_massey(terms: &[BigInt]) -> Option<LinearRecurrence> {
+    let term_bits = terms.iter().map(BigInt::bits).max().unwrap_or(0);
+    let mut primes = (1..1 << 61)
+        .rev()
+        .map(|i: u64| 2 * i + 1)
+        .filter(|&p| is_prime(p));
+
+    let mut combined: Option<Combined> = None;
+    // primes whose recurrence is shorter than the combined one, because they divide a determinant
+    let mut skipped = 0;
+    loop {
+        let p = primes.next()?;
+        let connection = MontgomeryField::new(p).connection_polynomial(terms);
+
+        let Some(current) = combined.as_mut() else {
+            combined = Some(Combined::new(p, &connection));
+            continue;
+        };
+        let order = current.coefficients.len() - 1;
+        let bits = coefficient_bits(order, term_bits);
+
+        if connection.len() < current.coefficients.len() {
+            skipped += 1;
+            // each of them divides a non-zero determinant, which only has so many 62 bit prime factors
+            if skipped * 61 > bits {
+                return None;
+            }
+            continue;
+        }
+        if connection.len() > current.coefficients.len() {
+            // the previous primes divided a determinant
+            skipped += current.primes;
+            *current = Combined::new(p, &connection);
+            continue;
+        }
+
+        let large_enough = current.modulus.bits() > bits + 1;
+        if current.add(p, &connection) {
+            if large_enough {
+                return None;
+            }
+        } else if generates(&current.coefficients, terms) {
+            let coefficients = current.coefficients[1..].iter().map(|c| -c).collect();
+            return Some(recurrence_from(coefficients, terms));
+        } else if large_enough {
+            return None;
+        }
+    }
+}
+
+/// A pure Rust function to find the shortest linear recurrence modulo the prime `p` which generates `terms`,
+/// or a [`FibError::NonPrimeModulus`] if `p` is not prime, as every non-zero number needs an inverse
+pub fn berlekamp_massey_mod(terms: &[BigInt], p: u64) -> Result<LinearRecurrence, FibError> {
+    if !is_prime(p) {
+        return Err(FibError::NonPrimeModulus { m: p });
+    }
+
+    let field = PrimeField(p);
+    let reduced = terms
+        .iter()
+        .map(|x| reduce(x, p.into()) as u64)
+        .collect::<Vec<_>>();
+    let coefficients = connection_polynomial(&field, &reduced)[1..]
+        .iter()
+        .map(|&c| field.sub(0, c).into())
+        .collect();
+    let terms = reduced.into_iter().map(BigInt::from).collect::<Vec<_>>();
+
+    recurrence_from(coefficients, &terms).with_modulus(p.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn integers(values: &[i64]) -> Vec<BigInt> {
+        values.iter().copied().map(BigInt::from).collect()
+    }
+
+    #[test]
+    fn finds_known_recurrences() {
+        let cases = [
+            // fibonacci
+            (&[1, 1][..], &[0, 1][..]),
+            // tribonacci
+            (&[1, 1, 1], &[0, 0, 1]),
+            // padovan
+            (&[0, 1, 1], &[1, 1, 1]),
+            (&[3, 0, -2], &[5, -1, 7]),
+            (&[2], &[1]),
+        ];
+
+        for (coefficients, initial) in cases {
+            let recurrence = LinearRecurrence::new(integers(coefficients), integers(initial));
+            let terms = recurrence.iter().take(40).collect::<Vec<_>>();
+
+            assert_eq!(berlekamp_massey(&terms), Some(recurrence.clone()));
+
+            let p = 1_000_000_007;
+            let expected = recurrence.with_modulus(p.into()).unwrap();
+            assert_eq!(berlekamp_massey_mod(&terms, p), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn zeros_and_non_integer_recurrences() {
+        let zeros = LinearRecurrence::new(integers(&[0]), integers(&[0]));
+        assert_eq!(berlekamp_massey(&integers(&[0, 0, 0])), Some(zeros.clone()));
+        assert_eq!(berlekamp_massey(&[]), Some(zeros));
+
+        // a_n = a_(n - 1) / 2 can't be written with integer coefficients
+        assert_eq!(berlekamp_massey(&integers(&[4, 2, 1])), None);
+    }
+
+    #[test]
+    fn jumps_to_later_terms() {
+        // the sequence of squares satisfies a_n = 3 * a_(n - 1) - 3 * a_(n - 2) + a_(n - 3)
+        let squares = (0..10)
+            .map(|n: i64| BigInt::from(n * n))
+            .collect::<Vec<_>>();
+        let recurrence = berlekamp_massey(&squares).unwrap();
+        assert_eq!(recurrence.order(), 3);
+        assert_eq!(
+            recurrence.nth(1_000_000),
+            BigInt::from(1_000_000_000_000i64)
+        );
+
+        let recurrence = berlekamp_massey_mod(&squares, 101).unwrap();
+        assert_eq!(recurrence.nth(1000), BigInt::from(1_000_000 % 101));
+    }
+
+    #[test]
+    fn rejects_composite_moduli() {
+        let terms = integers(&[0, 1, 1, 2, 3, 5]);
+        assert_eq!(
+            berlekamp_massey_mod(&terms, 91),
+            Err(FibError::NonPrimeModulus { m: 91 })
+        );
+        assert_eq!(
+            berlekamp_massey_mod(&terms, 0),
+            Err(FibError::NonPrimeModulus { m: 0 })
+        );
+        assert!(berlekamp_massey_mod(&terms, 2).is_ok());
+    }
+
+    #[test]
+    fn long_sequences() {
+        // thousands of terms with thousands of bits each took hours with rational arithmetic
+        let coefficients = integers(&[3, -1, 4, -1, 5, -9, 2, -6, 5, -3]);
+        let recurrence = LinearRecurrence::new(coefficients, integers(&[1; 10]));
+        let terms = recurrence.iter().take(2000).collect::<Vec<_>>();
+        assert_eq!(berlekamp_massey(&terms), Some(recurrence));
+
+        // a_n = 2 / 3 * a_(n - 1)
+        let geometric = (0..2000)
+            .map(|n| BigInt::from(2).pow(n) * BigInt::from(3).pow(1999 - n))
+            .collect::<Vec<_>>();
+        assert_eq!(berlekamp_massey(&geometric), None);
+
+        // pseudo random terms only have a recurrence of half their length with huge rational coefficients
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let random = (0..200)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                BigInt::from(state as i64)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(berlekamp_massey(&random), None);
+    }
+}
@@ -0,0 +1,260 @@
+//! Fibonacci coding: a self-delimiting code for positive integers
+//!
+//! A codeword holds the Zeckendorf bits of a value, starting at F(2), followed by an extra one.
+//! Zeckendorf representations never contain two consecutive ones, so the first `11` always ends a codeword.
+//! The bits are packed into bytes starting at the most significant bit, the last byte of a stream is padded with zeros.
+
+use std::fmt;
+
+use crate::{FIB_U128, MAX_U64_N};
+
+/// Everything that can go wrong while encoding or decoding a stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodingError {
+    /// Only positive integers have a codeword, `position` is the index of the value in the stream
+    NotPositive { position: usize },
+    /// The codeword at `position` describes a value which does not fit into 64 bits
+    ValueTooLarge { position: usize },
+    /// The stream ended in the middle of a codeword
+    Truncated,
+}
+
+impl fmt::Display for CodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodingError::NotPositive { position } => write!(
+                f,
+                "Only positive integers can be fibonacci coded, got a zero at position {position}"
+            ),
+            CodingError::ValueTooLarge { position } => write!(
+                f,
+                "The codeword at position {position} describes a value larger than 64 bits"
+            ),
+            CodingError::Truncated => write!(f, "The stream ended in the middle of a codeword"),
+        }
+    }
+}
+
+impl std::error::Error for CodingError {}
+
+/// Returns the codeword of `value` with the first bit of the stream in bit 0, and its length
+fn codeword(value: u64) -> (u128, u32) {
+    let mut remaining = u128::from(value);
+    let largest = FIB_U128[2..].partition_point(|&f| f <= remaining) + 1;
+
+    // F(i) is stored in bit i - 2, so the terminating one goes right after F(largest)
+    let mut code = 1 << (largest - 1);
+    for index in (2..=largest).rev() {
+        if FIB_U128[index] <= remaining {
+            remaining -= FIB_U128[index];
+            code |= 1 << (index - 2);
+        }
+    }
+
+    (code, largest as u32)
+}
+
+/// Incrementally turns positive integers into a fibonacci coded bitstream
+#[derive(Debug, Default)]
+pub struct Encoder {
+    bytes: Vec<u8>,
+    current: u8,
+    bits: u32,
+    count: usize,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends the codeword of `value` to the stream
+    pub fn write(&mut self, value: u64) -> Result<(), CodingError> {
+        if value == 0 {
+            return Err(CodingError::NotPositive {
+                position: self.count,
+            });
+        }
+
+        let (code, len) = codeword(value);
+        for bit in 0..len {
+            self.current |= (((code >> bit) & 1) as u8) << (7 - self.bits);
+            self.bits += 1;
+            if self.bits == 8 {
+                self.bytes.push(self.current);
+                self.current = 0;
+                self.bits = 0;
+            }
+        }
+
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Removes and returns the bytes which are complete so far
+    pub fn take_bytes(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.bytes)
+    }
+
+    /// Pads the last byte with zeros and returns the remaining bytes of the stream
+    pub fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.current);
+        }
+        self.bytes
+    }
+}
+
+/// Incrementally turns a fibonacci coded bitstream back into integers,
+/// codewords may be split across any number of chunks
+#[derive(Debug)]
+pub struct Decoder {
+    value: u64,
+    /// the fibonacci number the next bit stands for
+    index: usize,
+    previous: bool,
+    count: usize,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self {
+            value: 0,
+            index: 2,
+            previous: false,
+            count: 0,
+        }
+    }
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes the next chunk of the stream, appending every completed value to `values`
+    pub fn feed(&mut self, bytes: &[u8], values: &mut Vec<u64>) -> Result<(), CodingError> {
+        for &byte in bytes {
+            for shift in (0..8).rev() {
+                let bit = (byte >> shift) & 1 == 1;
+
+                if bit && self.previous {
+                    values.push(self.value);
+                    *self = Self {
+                        count: self.count + 1,
+                        ..Self::default()
+                    };
+                    continue;
+                }
+
+                if bit {
+                    let too_large = CodingError::ValueTooLarge {
+                        position: self.count,
+                    };
+                    if self.index > MAX_U64_N as usize {
+                        return Err(too_large);
+                    }
+                    self.value = self
+                        .value
+                        .checked_add(FIB_U128[self.index] as u64)
+                        .ok_or(too_large)?;
+                }
+
+                self.previous = bit;
+                self.index += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the stream did not end in the middle of a codeword
+    ///
+    /// Up to 7 trailing zeros are accepted as padding.
+    pub fn finish(self) -> Result<(), CodingError> {
+        if self.value == 0 && self.index - 2 < 8 {
+            Ok(())
+        } else {
+            Err(CodingError::Truncated)
+        }
+    }
+}
+
+/// A pure Rust function to fibonacci code all `values` into one stream
+pub fn encode(values: &[u64]) -> Result<Vec<u8>, CodingError> {
+    let mut encoder = Encoder::new();
+    for &value in values {
+        encoder.write(value)?;
+    }
+    Ok(encoder.finish())
+}
+
+/// A pure Rust function to decode a complete fibonacci coded stream
+pub fn decode(bytes: &[u8]) -> Result<Vec<u64>, CodingError> {
+    let mut decoder = Decoder::new();
+    let mut values = Vec::new();
+    decoder.feed(bytes, &mut values)?;
+    decoder.finish()?;
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_codewords() {
+        // 1 = 11, 2 = 011, 3 = 0011, 4 = 1011 => 1101 1001 1101 1000
+        assert_eq!(encode(&[1, 2, 3, 4]), Ok(vec![0b1101_1001, 0b1101_1000]));
+        assert_eq!(decode(&[0b1101_1001, 0b1101_1000]), Ok(vec![1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn roundtrips_in_chunks() {
+        let values = (1..2000)
+            .map(|i: u64| i.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+            .chain([1, u64::MAX, FIB_U128[93] as u64])
+            .collect::<Vec<_>>();
+        let bytes = encode(&values).unwrap();
+        assert_eq!(decode(&bytes), Ok(values.clone()));
+
+        for chunk_len in [1, 3, 64] {
+            let mut decoder = Decoder::new();
+            let mut decoded = Vec::new();
+            for chunk in bytes.chunks(chunk_len) {
+                decoder.feed(chunk, &mut decoded).unwrap();
+            }
+            decoder.finish().unwrap();
+            assert_eq!(decoded, values);
+        }
+    }
+
+    #[test]
+    fn invalid_streams() {
+        assert_eq!(
+            encode(&[1, 0]),
+            Err(CodingError::NotPositive { position: 1 })
+        );
+        // 0000 0011 is a complete codeword, but 0001 is not
+        assert_eq!(
+            decode(&[0b0000_0011, 0b0001_0000]),
+            Err(CodingError::Truncated)
+        );
+        assert_eq!(decode(&[0b1000_0000]), Err(CodingError::Truncated));
+        assert_eq!(decode(&[0; 2]), Err(CodingError::Truncated));
+        // F(93) + F(91) + F(89) is larger than u64::MAX
+        let mut too_large = vec![0u8; 12];
+        for index in [89, 91, 93, 94] {
+            too_large[(index - 2) / 8] |= 0b1000_0000 >> ((index - 2) % 8);
+        }
+        assert_eq!(
+            decode(&too_large),
+            Err(CodingError::ValueTooLarge { position: 0 })
+        );
+        // a codeword longer than any value
+        assert_eq!(
+            decode(&[0b1100_0000, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0b0100_0000]),
+            Err(CodingError::ValueTooLarge { position: 1 })
+        );
+    }
+}
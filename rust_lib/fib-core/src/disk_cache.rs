@@ -0,0 +1,476 @@
+//! An opt-in cache of expensive results on disk, so they survive the process computing them
+//!
+//! Every result is stored in its own file, named after a hash of its key (the function and its arguments):
+//!
+//! ```text
+//! magic "FIBC" | key length (u32) | key | limb count (u64) | little endian u64 limbs | checksum (u64)
+//! ```
+//!
+//! All integers are little endian and the checksum covers everything before it.
+//! Files are written to a temporary file first and then renamed into place, so other processes
+//! sharing the directory either see a complete file or none at all. Corrupted files and hash
+//! collisions are detected on reads and treated like misses.
+//!
+//! Reading a file bumps its modification time, the least recently used files are deleted
+//! once the files in the directory are larger than the size limit.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use num_bigint::{BigInt, BigUint};
+
+use crate::error::FibError;
+use crate::modular::fibonacci_mod;
+use crate::{FibonacciNumber, SignedFibonacciNumber, fibonacci_big, fibonacci_signed};
+
+/// The size limit of the cache directory if none is given, 1 GiB
+pub const DEFAULT_CACHE_SIZE: u64 = 1 << 30;
+
+/// Fibonacci numbers with smaller indices are faster to compute than to read from disk
+pub const MIN_CACHED_INDEX: u32 = 1 << 18;
+
+/// Indices of F(n) mod m with fewer bits are faster to compute than to read from disk
+pub const MIN_CACHED_MOD_BITS: u64 = 1 << 10;
+
+const MAGIC: &[u8; 4] = b"FIBC";
+
+const EXTENSION: &str = "fib";
+
+/// Temporary files older than this are left over from crashed writers
+const STALE_TEMPORARY_FILE: Duration = Duration::from_secs(60 * 60);
+
+/// The cache used by the `_cached` functions, if one has been set
+static CACHE: RwLock<Option<Arc<DiskCache>>> = RwLock::new(None);
+
+/// Starts caching expensive results in `dir`, which is created if it doesn't exist,
+/// and deletes the least recently used files once they are larger than `max_size` bytes in total
+///
+/// `None` stops caching, but keeps the files. Multiple processes can share the same directory.
+pub fn set_cache_dir(dir: Option<PathBuf>, max_size: u64) -> io::Result<()> {
+    let cache = dir.map(|dir| DiskCache::open(dir, max_size)).transpose()?;
+    *CACHE.write().unwrap() = cache.map(Arc::new);
+    Ok(())
+}
+
+/// [`fibonacci_signed`], reading and writing the results of large indices from the cache directory
+pub fn fibonacci_signed_cached(n: i64) -> Result<SignedFibonacciNumber, FibError> {
+    let index = match u32::try_from(n.unsigned_abs()) {
+        Ok(index) if index >= MIN_CACHED_INDEX => index,
+        _ => return fibonacci_signed(n),
+    };
+
+    // F(-n) = ±F(n), so both share the same file
+    let magnitude = get_or_compute(&CacheKey::fibonacci(index), || Ok(fibonacci_big(index)))?;
+    Ok(SignedFibonacciNumber::for_index(
+        n,
+        FibonacciNumber::Big(magnitude),
+    ))
+}
+
+/// [`fibonacci_mod`], reading and writing the results of large indices from the cache directory
+///
+/// Only moduli which don't fit into a u64 are cached, smaller ones reduce `n` by their
+/// (in memory cached) pisano period, which is faster than reading a file for any `n`.
+pub fn fibonacci_mod_cached(n: &BigInt, m: u128) -> Result<u128, FibError> {
+    if n.bits() < MIN_CACHED_MOD_BITS || u64::try_from(m).is_ok() {
+        return fibonacci_mod(n, m);
+    }
+
+    let result = get_or_compute(&CacheKey::fibonacci_mod(n, m), || {
+        fibonacci_mod(n, m).map(BigUint::from)
+    })?;
+    // the file has the right key and checksum, so it holds a value below `m`
+    Ok(u128::try_from(result).expect("cached results modulo a u128 fit into a u128"))
+}
+
+/// Looks `key` up in the cache, or computes and stores it if it is missing or caching is disabled
+fn get_or_compute<E>(
+    key: &CacheKey,
+    compute: impl FnOnce() -> Result<BigUint, E>,
+) -> Result<BigUint, E> {
+    // don't hold the lock while doing IO
+    let Some(cache) = CACHE.read().unwrap().clone() else {
+        return compute();
+    };
+
+    if let Some(value) = cache.get(key) {
+        return Ok(value);
+    }
+
+    let value = compute()?;
+    // the cache is only an optimization, failing to fill it must not fail the computation
+    let _ = cache.insert(key, &value);
+    Ok(value)
+}
+
+/// The function and arguments a result is stored under
+struct CacheKey(Vec<u8>);
+
+impl CacheKey {
+    fn fibonacci(n: u32) -> Self {
+        let mut key = b"fibonacci:".to_vec();
+        key.extend(n.to_le_bytes());
+        Self(key)
+    }
+
+    fn fibonacci_mod(n: &BigInt, m: u128) -> Self {
+        let mut key = b"fibonacci_mod:".to_vec();
+        key.extend(m.to_le_bytes());
+        key.extend(n.to_signed_bytes_le());
+        Self(key)
+    }
+
+    /// The name of the file the result is stored in
+    fn file_name(&self) -> String {
+        format!("{:016x}.{EXTENSION}", fnv1a(&self.0))
+    }
+}
+
+/// The 64 bit FNV-1a hash, which is used for file names and checksums because it is stable
+/// across Rust versions, unlike the hashers in `std`
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// A directory of cached results, see the [module documentation](self)
+struct DiskCache {
+    dir: PathBuf,
+    max_size: u64,
+}
+
+impl DiskCache {
+    fn open(dir: PathBuf, max_size: u64) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir, max_size })
+    }
+
+    fn path(&self, key: &CacheKey) -> PathBuf {
+        self.dir.join(key.file_name())
+    }
+
+    /// Reads the value stored under `key`, if there is an intact file for it
+    fn get(&self, key: &CacheKey) -> Option<BigUint> {
+        let path = self.path(key);
+        let mut bytes = Vec::new();
+        File::open(&path).ok()?.read_to_end(&mut bytes).ok()?;
+
+        let value = decode(&bytes, key)?;
+        touch(&path);
+        Some(value)
+    }
+
+    /// Stores `value` under `key` and evicts the least recently used files if the cache got too large
+    fn insert(&self, key: &CacheKey, value: &BigUint) -> io::Result<()> {
+        let bytes = encode(key, value);
+        if bytes.len() as u64 > self.max_size {
+            return Ok(());
+        }
+
+        // unique across threads by the counter and across processes by the pid
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let path = self.path(key);
+        let temporary = path.with_extension(format!(
+            "{}.{}.tmp",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let written = write_file(&temporary, &bytes).and_then(|()| fs::rename(&temporary, &path));
+        if written.is_err() {
+            let _ = fs::remove_file(&temporary);
+        }
+        written?;
+
+        self.evict()
+    }
+
+    /// Deletes the least recently used files until the cache fits into its size limit
+    fn evict(&self) -> io::Result<()> {
+        let now = SystemTime::now();
+        let mut files = Vec::new();
+        let mut size = 0;
+
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            // other processes may delete files at the same time
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let modified = metadata.modified()?;
+
+            match path.extension().and_then(|extension| extension.to_str()) {
+                Some(EXTENSION) => {
+                    size += metadata.len();
+                    files.push((modified, metadata.len(), path));
+                }
+                Some("tmp")
+                    if now
+                        .duration_since(modified)
+                        .is_ok_and(|age| age > STALE_TEMPORARY_FILE) =>
+                {
+                    let _ = fs::remove_file(&path);
+                }
+                _ => {}
+            }
+        }
+
+        files.sort_unstable();
+        for (_, len, path) in files {
+            if size <= self.max_size {
+                break;
+            }
+
+            match fs::remove_file(&path) {
+                Ok(()) => size -= len,
+                // another process evicted it first
+                Err(error) if error.kind() == ErrorKind::NotFound => size -= len,
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn write_file(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create_new(path)?);
+    file.write_all(bytes)?;
+    // make sure the contents are on disk before the file becomes visible under its final name
+    file.into_inner()?.sync_all()
+}
+
+/// Marks the file as recently used, for the eviction order
+fn touch(path: &Path) {
+    // opening the file for writing doesn't truncate it, but it is needed to set the time on Windows
+    let _ = File::options()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_modified(SystemTime::now()));
+}
+
+fn encode(key: &CacheKey, value: &BigUint) -> Vec<u8> {
+    let limbs = value.to_u64_digits();
+
+    let mut bytes = Vec::with_capacity(MAGIC.len() + 4 + key.0.len() + 8 * (limbs.len() + 2));
+    bytes.extend(MAGIC);
+    bytes.extend((key.0.len() as u32).to_le_bytes());
+    bytes.extend(&key.0);
+    bytes.extend((limbs.len() as u64).to_le_bytes());
+    for limb in limbs {
+        bytes.extend(limb.to_le_bytes());
+    }
+    bytes.extend(fnv1a(&bytes).to_le_bytes());
+    bytes
+}
+
+/// Decodes a file written by [`encode`], or returns `None` if it is corrupted or holds a different key
+fn decode(bytes: &[u8], key: &CacheKey) -> Option<BigUint> {
+    let (contents, checksum) = bytes.split_last_chunk::<8>()?;
+    if fnv1a(contents) != u64::from_le_bytes(*checksum) {
+        return None;
+    }
+
+    let contents = contents.strip_prefix(MAGIC)?;
+    let (key_len, contents) = contents.split_first_chunk::<4>()?;
+    let (stored_key, contents) = contents.split_at_checked(u32::from_le_bytes(*key_len) as usize)?;
+    if stored_key != key.0 {
+        return None;
+    }
+
+    let (limb_count, limbs) = contents.split_first_chunk::<8>()?;
+    if (limbs.len() / 8) as u64 != u64::from_le_bytes(*limb_count) || limbs.len() % 8 != 0 {
+        return None;
+    }
+
+    // little endian limbs are the little endian bytes of the whole number
+    Some(BigUint::from_bytes_le(limbs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory for each test, which is deleted afterwards
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir()
+                .join(format!("fib-core-disk-cache-{}-{name}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn files(dir: &Path) -> Vec<PathBuf> {
+        let mut files = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = TempDir::new("round_trip");
+        let cache = DiskCache::open(dir.0.clone(), DEFAULT_CACHE_SIZE).unwrap();
+
+        let key = CacheKey::fibonacci(5000);
+        assert_eq!(cache.get(&key), None);
+
+        let value = fibonacci_big(5000);
+        cache.insert(&key, &value).unwrap();
+        assert_eq!(cache.get(&key), Some(value));
+        assert_eq!(cache.get(&CacheKey::fibonacci(5001)), None);
+
+        // zero has no limbs at all
+        let key = CacheKey::fibonacci_mod(&BigInt::from(-15), 5);
+        cache.insert(&key, &BigUint::ZERO).unwrap();
+        assert_eq!(cache.get(&key), Some(BigUint::ZERO));
+
+        assert_eq!(files(&dir.0).len(), 2);
+    }
+
+    #[test]
+    fn corrupted_files_are_misses() {
+        let dir = TempDir::new("corrupted");
+        let cache = DiskCache::open(dir.0.clone(), DEFAULT_CACHE_SIZE).unwrap();
+        let key = CacheKey::fibonacci(1000);
+        cache.insert(&key, &fibonacci_big(1000)).unwrap();
+
+        let path = cache.path(&key);
+        let bytes = fs::read(&path).unwrap();
+
+        let mut flipped = bytes.clone();
+        flipped[40] ^= 1;
+        fs::write(&path, flipped).unwrap();
+        assert_eq!(cache.get(&key), None);
+
+        fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+        assert_eq!(cache.get(&key), None);
+
+        // a different key whose hash collides with the file name
+        fs::write(
+            &path,
+            encode(&CacheKey::fibonacci(1001), &BigUint::from(1u8)),
+        )
+        .unwrap();
+        assert_eq!(cache.get(&key), None);
+
+        cache.insert(&key, &fibonacci_big(1000)).unwrap();
+        assert_eq!(cache.get(&key), Some(fibonacci_big(1000)));
+    }
+
+    #[test]
+    fn least_recently_used_files_are_evicted() {
+        let dir = TempDir::new("eviction");
+        let value = fibonacci_big(10_000);
+        let size = encode(&CacheKey::fibonacci(0), &value).len() as u64;
+        let cache = DiskCache::open(dir.0.clone(), 3 * size).unwrap();
+
+        // set the times explicitly, file systems may not resolve them finely enough
+        let start = SystemTime::now() - Duration::from_secs(60);
+        for n in 0..3 {
+            cache.insert(&CacheKey::fibonacci(n), &value).unwrap();
+            File::options()
+                .write(true)
+                .open(cache.path(&CacheKey::fibonacci(n)))
+                .unwrap()
+                .set_modified(start + Duration::from_secs(n.into()))
+                .unwrap();
+        }
+
+        assert!(cache.get(&CacheKey::fibonacci(0)).is_some());
+        cache.insert(&CacheKey::fibonacci(3), &value).unwrap();
+
+        assert_eq!(files(&dir.0).len(), 3);
+        assert!(cache.get(&CacheKey::fibonacci(1)).is_none());
+        for n in [0, 2, 3] {
+            assert!(cache.get(&CacheKey::fibonacci(n)).is_some(), "F({n})");
+        }
+
+        // values which could never fit aren't written at all
+        let small = DiskCache::open(dir.0.clone(), size - 1).unwrap();
+        small.insert(&CacheKey::fibonacci(4), &value).unwrap();
+        assert!(cache.get(&CacheKey::fibonacci(4)).is_none());
+    }
+
+    #[test]
+    fn concurrent_writers_and_readers() {
+        let dir = TempDir::new("concurrent");
+        let value = fibonacci_big(100_000);
+        let size = encode(&CacheKey::fibonacci(0), &value).len() as u64;
+        // small enough that the writers keep evicting each other's files
+        let cache = DiskCache::open(dir.0.clone(), 4 * size).unwrap();
+
+        std::thread::scope(|scope| {
+            for thread in 0..8 {
+                let (cache, value) = (&cache, &value);
+                scope.spawn(move || {
+                    for i in 0..50 {
+                        let key = CacheKey::fibonacci((thread + i) % 6);
+                        if let Some(read) = cache.get(&key) {
+                            assert_eq!(&read, value);
+                        }
+                        cache.insert(&key, value).unwrap();
+                    }
+                });
+            }
+        });
+
+        let files = files(&dir.0);
+        assert!(files.len() <= 4, "{files:?}");
+        assert!(
+            files
+                .iter()
+                .all(|file| file.extension() == Some(EXTENSION.as_ref()))
+        );
+    }
+
+    #[test]
+    fn cached_functions_match_the_uncached_ones() {
+        let dir = TempDir::new("global");
+        set_cache_dir(Some(dir.0.clone()), DEFAULT_CACHE_SIZE).unwrap();
+
+        // even, so F(-n) is negative
+        let n = i64::from(MIN_CACHED_INDEX);
+        let big_n = BigInt::from(3).pow(1000);
+        for _ in 0..2 {
+            assert_eq!(fibonacci_signed_cached(n), fibonacci_signed(n));
+            assert_eq!(fibonacci_signed_cached(-n), fibonacci_signed(-n));
+            assert_eq!(fibonacci_signed_cached(10), fibonacci_signed(10));
+
+            assert_eq!(
+                fibonacci_mod_cached(&big_n, 1_000_000_007),
+                fibonacci_mod(&big_n, 1_000_000_007)
+            );
+            assert_eq!(
+                fibonacci_mod_cached(&-&big_n, u128::MAX),
+                fibonacci_mod(&-&big_n, u128::MAX)
+            );
+            assert_eq!(
+                fibonacci_mod_cached(&big_n, 0),
+                Err(FibError::InvalidModulus)
+            );
+        }
+        // F(-n) shares the file of F(n), small indices and moduli aren't cached
+        assert_eq!(files(&dir.0).len(), 2);
+
+        set_cache_dir(None, DEFAULT_CACHE_SIZE).unwrap();
+        assert_eq!(fibonacci_signed_cached(n), fibonacci_signed(n));
+    }
+}
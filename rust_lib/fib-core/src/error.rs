@@ -0,0 +1,44 @@
+//! The error type shared by all fibonacci functions
+
+use std::fmt;
+
+/// Everything that can go wrong while computing fibonacci numbers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FibError {
+    /// F(n) does not fit into the integer type it was requested as,
+    /// `max_n` is the largest index which still fits
+    Overflow { n: i64, max_n: i64 },
+    /// Modular arithmetic needs a modulus of at least one
+    InvalidModulus,
+    /// The algorithm needs a prime modulus, so that every non-zero number has an inverse
+    NonPrimeModulus { m: u64 },
+    /// A recurrence which is already reduced modulo `modulus` can't be computed modulo another `m`
+    ModulusMismatch { m: u128, modulus: u128 },
+    /// The function only supports non-negative indices
+    NegativeIndex { n: i64 },
+    /// The index is so large that computing the result is not supported
+    ResourceLimit { limit: u64 },
+}
+
+impl fmt::Display for FibError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FibError::Overflow { n, max_n } => write!(
+                f,
+                "Overflow occurred while computing the {n}th fibonacci number (the largest index which fits is {max_n})"
+            ),
+            FibError::InvalidModulus => write!(f, "The modulus must not be zero"),
+            FibError::NonPrimeModulus { m } => write!(f, "The modulus has to be prime, got {m}"),
+            FibError::ModulusMismatch { m, modulus } => write!(
+                f,
+                "The recurrence is reduced modulo {modulus}, so it can't be computed modulo {m}"
+            ),
+            FibError::NegativeIndex { n } => write!(f, "The index must not be negative, got {n}"),
+            FibError::ResourceLimit { limit } => {
+                write!(f, "Indices larger than {limit} are not supported")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FibError {}
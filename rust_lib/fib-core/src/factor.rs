@@ -0,0 +1,149 @@
+//! Integer factorization for 64 bit numbers, using Miller-Rabin and Pollard's rho
+
+/// Computes `(a * b) mod m` for 64 bit numbers
+pub(crate) fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    (a as u128 * b as u128 % m as u128) as u64
+}
+
+/// Computes `(base ^ exponent) mod m` by square and multiply
+pub(crate) fn pow_mod(mut base: u64, mut exponent: u64, m: u64) -> u64 {
+    let mut result = 1 % m;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exponent >>= 1;
+    }
+
+    result
+}
+
+/// A deterministic Miller-Rabin primality test
+///
+/// Testing the first 12 primes as witnesses is enough for every 64 bit number
+pub fn is_prime(n: u64) -> bool {
+    const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+    if n < 2 {
+        return false;
+    }
+    for p in WITNESSES {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+
+    WITNESSES.iter().all(|&a| {
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+/// Finds a non-trivial factor of the odd composite number `n` using Pollard's rho
+fn pollard_rho(n: u64) -> u64 {
+    // if a starting value cycles without finding a factor we just try the next one
+    for c in 1.. {
+        let f = |x: u64| ((x as u128 * x as u128 + c) % n as u128) as u64;
+
+        let (mut x, mut y) = (2, 2);
+        let mut factor = 1;
+        while factor == 1 {
+            x = f(x);
+            y = f(f(y));
+            factor = gcd(x.abs_diff(y), n);
+        }
+
+        if factor != n {
+            return factor;
+        }
+    }
+
+    unreachable!("every composite number has a non-trivial factor")
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
+
+/// Factors `n` into its prime powers, sorted by prime
+pub fn factorize(n: u64) -> Vec<(u64, u32)> {
+    let mut primes = Vec::new();
+    let mut remaining = n;
+
+    // small factors are much cheaper to find by trial division
+    for p in (2..1000).filter(|&p| is_prime(p)) {
+        while remaining.is_multiple_of(p) {
+            primes.push(p);
+            remaining /= p;
+        }
+    }
+
+    let mut composites = vec![remaining];
+    while let Some(n) = composites.pop() {
+        if n == 1 {
+            continue;
+        }
+        if is_prime(n) {
+            primes.push(n);
+        } else {
+            let factor = pollard_rho(n);
+            composites.extend([factor, n / factor]);
+        }
+    }
+
+    primes.sort_unstable();
+
+    let mut factors: Vec<(u64, u32)> = Vec::new();
+    for p in primes {
+        match factors.last_mut() {
+            Some((last, exponent)) if *last == p => *exponent += 1,
+            _ => factors.push((p, 1)),
+        }
+    }
+
+    factors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn factors_multiply_back_to_n() {
+        let numbers = [
+            1,
+            2,
+            360,
+            1_000_000_007,
+            600_851_475_143,
+            4_294_967_291 * 4_294_967_279,
+            u64::MAX,
+        ];
+        for n in numbers {
+            let factors = factorize(n);
+            assert!(
+                factors.iter().all(|&(p, _)| is_prime(p)),
+                "{n}: {factors:?}"
+            );
+            let product: u64 = factors.iter().map(|&(p, e)| p.pow(e)).product();
+            assert_eq!(product, n, "{factors:?}");
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Recognising fibonacci numbers and recovering their index
+//!
+//! Negative integers are negafibonacci numbers if they are F(-n) for an even n,
+//! as F(-n) = (-1)^(n + 1) * F(n).
+
+use std::f64::consts::LN_2;
+
+use num_bigint::{BigInt, BigUint, Sign};
+
+use crate::error::FibError;
+use crate::{FIB_U128, MAX_INDEX, fibonacci_pair_big};
+
+/// ln(φ), to estimate indices from the size of a fibonacci number
+const LN_PHI: f64 = 0.481_211_825_059_603_45;
+
+/// A pure Rust function to check whether `x` is a (nega)fibonacci number
+///
+/// Uses the identity 5F(n)² + 4(-1)^n = L(n)², so `x` is a fibonacci number
+/// exactly if 5x² + 4 or 5x² - 4 is a perfect square.
+pub fn is_fibonacci(x: &BigInt) -> bool {
+    let negative = x.sign() == Sign::Minus;
+    if let Ok(magnitude) = u128::try_from(x.magnitude()) {
+        return index_small(magnitude, negative).is_some();
+    }
+
+    let five_squared = BigUint::from(5u32) * x.magnitude() * x.magnitude();
+    // the sign of the correction is the parity of the index, and negative values need an even one
+    is_square(&(&five_squared + 4u32)) || (!negative && is_square(&(five_squared - 4u32)))
+}
+
+/// A pure Rust function to compute the index of `x` in the fibonacci sequence,
+/// or None if it is not a (nega)fibonacci number
+///
+/// Non-negative values get the smallest non-negative index, so 1 maps to 1 rather than 2.
+/// Negative values get their unique (even) negative index.
+pub fn fibonacci_index(x: &BigInt) -> Result<Option<i64>, FibError> {
+    let negative = x.sign() == Sign::Minus;
+    let magnitude = x.magnitude();
+    if let Ok(magnitude) = u128::try_from(magnitude) {
+        return Ok(index_small(magnitude, negative));
+    }
+
+    let Some(n) = index_big(magnitude)? else {
+        return Ok(None);
+    };
+
+    Ok(match negative {
+        false => Some(n),
+        true if n % 2 == 0 => Some(-n),
+        true => None,
+    })
+}
+
+/// Looks the index of `magnitude` up in the table of all fibonacci numbers fitting into 128 bits
+fn index_small(magnitude: u128, negative: bool) -> Option<i64> {
+    let n = FIB_U128.partition_point(|&f| f < magnitude);
+    if FIB_U128.get(n) != Some(&magnitude) {
+        return None;
+    }
+
+    match (negative, n) {
+        (false, n) => Some(n as i64),
+        // -1 is F(-2), as F(-1) = 1
+        (true, 1) => Some(-2),
+        (true, n) if n.is_multiple_of(2) => Some(-(n as i64)),
+        (true, _) => None,
+    }
+}
+
+/// Estimates the index of `magnitude` from F(n) ≈ φ^n / √5 and confirms it against the actual fibonacci numbers
+fn index_big(magnitude: &BigUint) -> Result<Option<i64>, FibError> {
+    let estimate = estimate_index(magnitude).round();
+
+    if estimate > f64::from(MAX_INDEX) {
+        return Err(FibError::ResourceLimit {
+            limit: MAX_INDEX.into(),
+        });
+    }
+
+    // check the neighbours as well, in case rounding went the wrong way.
+    // `magnitude` doesn't fit into a u128, so `n` is far above 0, but it can be `MAX_INDEX`.
+    let n = estimate as u32;
+    let (previous, current) = fibonacci_pair_big(n - 1);
+    let next = n.checked_add(1).map(|next| (next, &previous + &current));
+    let index = [(n - 1, previous), (n, current)]
+        .into_iter()
+        .chain(next)
+        .find(|(_, f)| f == magnitude)
+        .map(|(n, _)| n.into());
+
+    Ok(index)
+}
+
+/// The real `n` with φ^n / √5 = `magnitude`, which is within rounding of the index of a fibonacci number
+pub(crate) fn estimate_index(magnitude: &BigUint) -> f64 {
+    // the leading 64 bits are plenty for a floating point logarithm
+    let shift = magnitude.bits().saturating_sub(64);
+    let leading = u64::try_from(magnitude >> shift).unwrap();
+    let ln = (leading as f64).ln() + shift as f64 * LN_2;
+    (ln + 0.5 * 5f64.ln()) / LN_PHI
+}
+
+fn is_square(n: &BigUint) -> bool {
+    let root = n.sqrt();
+    &root * &root == *n
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fibonacci_big, fibonacci_signed};
+
+    #[test]
+    fn finds_the_index_of_every_fibonacci_number() {
+        for n in -400i64..400 {
+            let f = BigInt::from(fibonacci_signed(n).unwrap());
+            // positive values always get their non-negative index, which is 1 rather than 2 for F(2) = 1
+            let expected = match n {
+                n if n < 0 && n % 2 != 0 => -n,
+                2 => 1,
+                n => n,
+            };
+            assert!(is_fibonacci(&f), "F({n})");
+            assert_eq!(fibonacci_index(&f), Ok(Some(expected)), "F({n})");
+        }
+    }
+
+    #[test]
+    fn rejects_neighbours_of_fibonacci_numbers() {
+        for n in 5..400 {
+            let f = BigInt::from(fibonacci_big(n));
+            for x in [&f - 1, &f + 1, -&f + 1, -&f - 1] {
+                assert!(!is_fibonacci(&x), "{x}");
+                assert_eq!(fibonacci_index(&x), Ok(None), "{x}");
+            }
+        }
+
+        // odd indices have a positive F(-n)
+        for n in (5..400).step_by(2) {
+            let x = -BigInt::from(fibonacci_big(n));
+            assert!(!is_fibonacci(&x), "{x}");
+            assert_eq!(fibonacci_index(&x), Ok(None), "{x}");
+        }
+    }
+}
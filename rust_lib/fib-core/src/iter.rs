@@ -0,0 +1,175 @@
+//! A lazy iterator over the fibonacci numbers
+
+use num_bigint::BigUint;
+
+use crate::{FibonacciNumber, MAX_U128_N, fibonacci_pair, fibonacci_pair_big};
+
+/// The pair `(F(k), F(k + 1))`, which is only stored as [`BigUint`]s once F(k + 1) no longer fits into a u128
+#[derive(Debug, Clone)]
+enum Pair {
+    Small(u128, u128),
+    Big(BigUint, BigUint),
+}
+
+impl Pair {
+    /// Computes `(F(k), F(k + 1))` by fast doubling
+    fn at(k: u32) -> Self {
+        if k < MAX_U128_N {
+            let (a, b) = fibonacci_pair(k);
+            Pair::Small(a, b)
+        } else {
+            let (a, b) = fibonacci_pair_big(k);
+            Pair::Big(a, b)
+        }
+    }
+
+    /// Moves from `(F(k), F(k + 1))` to `(F(k + s), F(k + s + 1))`, where `step` is `(F(s - 1), F(s))`:
+    ///
+    /// F(k + s)     = F(k + 1) * F(s) + F(k) * F(s - 1)
+    /// F(k + s + 1) = F(k + 1) * F(s + 1) + F(k) * F(s)
+    fn advance(&mut self, step: &Pair) {
+        // a single step is one addition, (F(k), F(k + 1)) -> (F(k + 1), F(k) + F(k + 1))
+        if let Pair::Small(0, 1) = step {
+            match self {
+                Pair::Small(a, b) => {
+                    if let Some(sum) = a.checked_add(*b) {
+                        *self = Pair::Small(*b, sum);
+                        return;
+                    }
+                }
+                Pair::Big(a, b) => {
+                    *a += &*b;
+                    std::mem::swap(a, b);
+                    return;
+                }
+            }
+        }
+
+        if let (Pair::Small(a, b), Pair::Small(before, at)) = (&*self, step) {
+            let advanced = (|| {
+                let after = before.checked_add(*at)?;
+                Some(Pair::Small(
+                    b.checked_mul(*at)?.checked_add(a.checked_mul(*before)?)?,
+                    b.checked_mul(after)?.checked_add(a.checked_mul(*at)?)?,
+                ))
+            })();
+
+            if let Some(advanced) = advanced {
+                *self = advanced;
+                return;
+            }
+        }
+
+        // the numbers no longer fit into a u128, so we continue with `BigUint`s
+        let (a, b) = self.to_big();
+        let (before, at) = step.to_big();
+        let after = &before + &at;
+        *self = Pair::Big(&b * &at + &a * &before, &b * &after + &a * &at);
+    }
+
+    fn to_big(&self) -> (BigUint, BigUint) {
+        match self {
+            Pair::Small(a, b) => (BigUint::from(*a), BigUint::from(*b)),
+            Pair::Big(a, b) => (a.clone(), b.clone()),
+        }
+    }
+
+    fn first(&self) -> FibonacciNumber {
+        match self {
+            Pair::Small(a, _) => FibonacciNumber::Small(*a),
+            Pair::Big(a, _) => match u128::try_from(a) {
+                Ok(a) => FibonacciNumber::Small(a),
+                Err(_) => FibonacciNumber::Big(a.clone()),
+            },
+        }
+    }
+}
+
+/// A lazy iterator over `F(start), F(start + step), ...` up to, but excluding, `F(stop)`
+///
+/// Each step only takes a constant number of multiplications, instead of
+/// computing every number from scratch
+#[derive(Debug, Clone)]
+pub struct FibonacciIter {
+    /// The index of `current`, or None once the iterator ran past u32::MAX
+    index: Option<u32>,
+    stop: Option<u32>,
+    step: u32,
+    current: Pair,
+    /// `(F(step - 1), F(step))`
+    step_pair: Pair,
+}
+
+impl FibonacciIter {
+    /// Creates an iterator which yields every `step`th fibonacci number from `start` to `stop`
+    ///
+    /// `step` must not be zero
+    pub fn new(start: u32, stop: Option<u32>, step: u32) -> Self {
+        assert!(step > 0, "The step must not be zero");
+
+        Self {
+            index: Some(start),
+            stop,
+            step,
+            current: Pair::at(start),
+            step_pair: Pair::at(step - 1),
+        }
+    }
+
+    /// Jumps to the `n`th fibonacci number by fast doubling
+    pub fn seek(&mut self, n: u32) {
+        self.index = Some(n);
+        self.current = Pair::at(n);
+    }
+
+    /// Whether the numbers still fit into u128s, so advancing the iterator is cheap
+    pub fn is_small(&self) -> bool {
+        matches!(self.current, Pair::Small(..))
+    }
+
+    /// The index of the fibonacci number which will be returned next
+    pub fn index(&self) -> Option<u32> {
+        self.index
+            .filter(|&index| self.stop.is_none_or(|stop| index < stop))
+    }
+}
+
+impl Iterator for FibonacciIter {
+    type Item = FibonacciNumber;
+
+    fn next(&mut self) -> Option<FibonacciNumber> {
+        let index = self.index()?;
+        let value = self.current.first();
+
+        self.index = index.checked_add(self.step);
+        if self.index().is_some() {
+            self.current.advance(&self.step_pair);
+        }
+
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci_number;
+
+    #[test]
+    fn yields_every_step() {
+        for step in [1, 2, 3, 50, 185, 186, 187, 500] {
+            let expected = (7..3000).step_by(step as usize).map(fibonacci_number);
+            assert!(
+                FibonacciIter::new(7, Some(3000), step).eq(expected),
+                "step {step}"
+            );
+        }
+    }
+
+    #[test]
+    fn seeks_past_the_u128_range() {
+        let mut iter = FibonacciIter::new(0, None, 1);
+        iter.seek(1000);
+        assert!(iter.take(10).eq((1000..1010).map(fibonacci_number)));
+    }
+}
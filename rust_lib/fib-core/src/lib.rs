@@ -0,0 +1,345 @@
+//! Fibonacci numbers in pure Rust, the implementation behind the `rust_lib` python module
+//!
+//! - [`fibonacci`] and [`FIB_U128`] for everything which fits into a u128
+//! - [`fibonacci_big`], [`fibonacci_number`] and [`fibonacci_signed`] for arbitrary precision and negative indices
+//! - [`fibonacci_mod`], [`pisano_period`] and the [`lucas`](mod@lucas) sequences for modular arithmetic
+//! - [`FibonacciIter`] and [`fibonacci_range`] for consecutive fibonacci numbers
+//! - [`set_cache_dir`] to keep expensive results on disk, for [`fibonacci_signed_cached`] and [`fibonacci_mod_cached`]
+
+use num_bigint::{BigInt, BigUint, Sign};
+
+pub mod berlekamp_massey;
+pub mod coding;
+pub mod disk_cache;
+pub mod error;
+pub mod factor;
+pub mod inverse;
+pub mod iter;
+pub mod lucas;
+pub mod modular;
+pub mod pisano;
+pub mod range;
+pub mod recurrence;
+pub mod zeckendorf;
+
+pub use berlekamp_massey::{berlekamp_massey, berlekamp_massey_mod};
+pub use disk_cache::{fibonacci_mod_cached, fibonacci_signed_cached, set_cache_dir};
+pub use error::FibError;
+pub use inverse::{fibonacci_index, is_fibonacci};
+pub use iter::FibonacciIter;
+pub use lucas::{lucas, lucas_mod, lucas_u, lucas_u_mod, lucas_v, lucas_v_mod};
+pub use modular::fibonacci_mod;
+pub use pisano::{clear_pisano_cache, pisano_period};
+pub use range::fibonacci_range;
+pub use recurrence::{LinearRecurrence, LinearRecurrenceIter};
+pub use zeckendorf::{from_zeckendorf, zeckendorf};
+
+/// The largest index (in magnitude) for which exact fibonacci numbers are computed
+pub const MAX_INDEX: u32 = u32::MAX;
+
+/// The largest `n` for which the `n`th fibonacci number still fits into a u64
+pub const MAX_U64_N: u32 = 93;
+
+/// The largest `n` for which the `n`th fibonacci number still fits into a u128
+pub const MAX_U128_N: u32 = 186;
+
+/// All fibonacci numbers which fit into a u128, computed at compile time
+pub static FIB_U128: [u128; MAX_U128_N as usize + 1] = {
+    let mut table = [0; MAX_U128_N as usize + 1];
+    let mut n = 0;
+    while n < table.len() {
+        table[n] = match fibonacci(n as u32) {
+            Ok(value) => value,
+            Err(_) => unreachable!(),
+        };
+        n += 1;
+    }
+    table
+};
+
+/// [`FIB_U128`] as 16 byte little endian words, so it can be shared without copying
+pub static FIB_U128_LE_BYTES: [u8; FIB_U128.len() * 16] = {
+    let mut bytes = [0; FIB_U128.len() * 16];
+    let mut i = 0;
+    while i < bytes.len() {
+        bytes[i] = FIB_U128[i / 16].to_le_bytes()[i % 16];
+        i += 1;
+    }
+    bytes
+};
+
+/// A pure Rust function to compute the `n`th fibonacci number or an
+/// [`FibError::Overflow`] if it does not fit into a u128
+///
+/// Python will no be able to see this function unless you expose it in a `pyo3::pymodule`
+///
+/// This is a `const fn`, so it can also be evaluated at compile time.
+pub const fn fibonacci(n: u32) -> Result<u128, FibError> {
+    if n > MAX_U128_N {
+        return Err(FibError::Overflow {
+            n: n as i64,
+            max_n: MAX_U128_N as i64,
+        });
+    }
+
+    // F(n + 1) may already overflow, so only compute F(n) in the last step
+    let (a, b) = fibonacci_pair(n >> 1);
+    if n & 1 == 0 {
+        Ok(a * (2 * b - a))
+    } else {
+        Ok(a * a + b * b)
+    }
+}
+
+/// Computes `(F(n), F(n + 1))` using fast doubling:
+///
+/// F(2k)     = F(k) * (2 * F(k + 1) - F(k))
+/// F(2k + 1) = F(k)² + F(k + 1)²
+///
+/// `n` must be smaller than [`MAX_U128_N`], so that F(n + 1) still fits into a u128
+pub(crate) const fn fibonacci_pair(n: u32) -> (u128, u128) {
+    debug_assert!(n < MAX_U128_N);
+
+    let (mut a, mut b) = (0, 1);
+    // `for` loops are not allowed in a `const fn`
+    let mut shift = u32::BITS - n.leading_zeros();
+    while shift > 0 {
+        shift -= 1;
+        let even = a * (2 * b - a);
+        let odd = a * a + b * b;
+
+        (a, b) = if n >> shift & 1 == 1 {
+            (odd, even + odd)
+        } else {
+            (even, odd)
+        };
+    }
+
+    (a, b)
+}
+
+/// A fibonacci number, which is only stored as a [`BigUint`] once it no longer fits into a u128
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FibonacciNumber {
+    Small(u128),
+    Big(BigUint),
+}
+
+/// Computes the `n`th fibonacci number, looking it up in [`FIB_U128`] or using [`fibonacci_big`] depending on its size
+pub fn fibonacci_number(n: u32) -> FibonacciNumber {
+    match FIB_U128.get(n as usize) {
+        Some(&result) => FibonacciNumber::Small(result),
+        None => FibonacciNumber::Big(fibonacci_big(n)),
+    }
+}
+
+/// A fibonacci number for a signed index, which can be negative
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedFibonacciNumber {
+    negative: bool,
+    magnitude: FibonacciNumber,
+}
+
+impl SignedFibonacciNumber {
+    /// Whether the fibonacci number is negative, which is only the case for negative even indices
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// The absolute value of the fibonacci number
+    pub fn magnitude(&self) -> &FibonacciNumber {
+        &self.magnitude
+    }
+
+    /// Applies the sign of F(n) to `magnitude`, which has to be F(|n|)
+    fn for_index(n: i64, magnitude: FibonacciNumber) -> Self {
+        Self {
+            negative: negafibonacci_is_negative(n),
+            magnitude,
+        }
+    }
+}
+
+impl From<FibonacciNumber> for BigUint {
+    fn from(value: FibonacciNumber) -> BigUint {
+        match value {
+            FibonacciNumber::Small(value) => BigUint::from(value),
+            FibonacciNumber::Big(value) => value,
+        }
+    }
+}
+
+impl From<SignedFibonacciNumber> for BigInt {
+    fn from(value: SignedFibonacciNumber) -> BigInt {
+        let sign = if value.negative {
+            Sign::Minus
+        } else {
+            Sign::Plus
+        };
+
+        BigInt::from_biguint(sign, value.magnitude.into())
+    }
+}
+
+/// F(-n) = (-1)^(n + 1) * F(n), so F(n) is negative exactly for negative even `n`
+fn negafibonacci_is_negative(n: i64) -> bool {
+    n < 0 && n % 2 == 0
+}
+
+/// A pure Rust function to compute the `n`th fibonacci number for positive and negative `n`
+/// or a [`FibError::ResourceLimit`] if `|n|` is larger than [`MAX_INDEX`]
+pub fn fibonacci_signed(n: i64) -> Result<SignedFibonacciNumber, FibError> {
+    let magnitude = u32::try_from(n.unsigned_abs()).map_err(|_| FibError::ResourceLimit {
+        limit: MAX_INDEX.into(),
+    })?;
+
+    Ok(SignedFibonacciNumber::for_index(
+        n,
+        fibonacci_number(magnitude),
+    ))
+}
+
+/// Converts `n` into an index for the functions which only support non-negative indices
+pub fn unsigned_index(n: i64) -> Result<u32, FibError> {
+    u32::try_from(n).map_err(|_| {
+        if n < 0 {
+            FibError::NegativeIndex { n }
+        } else {
+            FibError::ResourceLimit {
+                limit: MAX_INDEX.into(),
+            }
+        }
+    })
+}
+
+/// A pure Rust function to compute the `n`th fibonacci number with arbitrary precision
+///
+/// The first doubling steps are done in a u128 until the numbers get too large,
+/// so this is only slower than [`fibonacci`] once the result no longer fits into a u128
+pub fn fibonacci_big(n: u32) -> BigUint {
+    fibonacci_pair_big(n).0
+}
+
+/// Computes `(F(n), F(n + 1))` with arbitrary precision, using the same fast doubling as [`fibonacci`]
+pub fn fibonacci_pair_big(n: u32) -> (BigUint, BigUint) {
+    // find the longest prefix of `n`'s bits which we can still handle with u128s
+    let mut shift = 0;
+    while n >> shift >= MAX_U128_N {
+        shift += 1;
+    }
+
+    let (a, b) = fibonacci_pair(n >> shift);
+    let (mut a, mut b) = (BigUint::from(a), BigUint::from(b));
+
+    while shift > 0 {
+        shift -= 1;
+
+        let even = &a * ((&b << 1u8) - &a);
+        let odd = &a * &a + &b * &b;
+
+        (a, b) = if n >> shift & 1 == 1 {
+            let next = &even + &odd;
+            (odd, next)
+        } else {
+            (even, odd)
+        };
+    }
+
+    (a, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The straightforward linear loop the fast doubling has to agree with
+    ///
+    /// F(n + 1) is allowed to overflow, as long as F(n) itself still fits
+    fn fibonacci_iterative(n: u32) -> Option<u128> {
+        let mut a: Option<u128> = Some(0);
+        let mut b: Option<u128> = Some(1);
+
+        for _ in 0..n {
+            (a, b) = (b, a.zip(b).and_then(|(a, b)| a.checked_add(b)));
+        }
+
+        a
+    }
+
+    #[test]
+    fn fast_doubling_matches_iterative_loop() {
+        for n in 0..=MAX_U128_N {
+            assert_eq!(fibonacci(n).ok(), fibonacci_iterative(n), "F({n})");
+            assert_eq!(
+                fibonacci(n).ok().map(BigUint::from),
+                Some(fibonacci_big(n)),
+                "F({n})"
+            );
+        }
+    }
+
+    #[test]
+    fn table_is_computed_at_compile_time() {
+        const F100: u128 = match fibonacci(100) {
+            Ok(value) => value,
+            Err(_) => panic!(),
+        };
+        assert_eq!(F100, 354_224_848_179_261_915_075);
+        assert_eq!(FIB_U128[100], F100);
+
+        for (n, word) in FIB_U128_LE_BYTES.chunks_exact(16).enumerate() {
+            assert_eq!(u128::from_le_bytes(word.try_into().unwrap()), FIB_U128[n]);
+            assert_eq!(fibonacci(n as u32), Ok(FIB_U128[n]));
+        }
+    }
+
+    #[test]
+    fn u128_path_stops_at_first_overflow() {
+        assert_eq!(fibonacci_iterative(MAX_U128_N + 1), None);
+        assert_eq!(
+            fibonacci(MAX_U128_N + 1),
+            Err(FibError::Overflow { n: 187, max_n: 186 })
+        );
+        assert!(fibonacci(u32::MAX).is_err());
+    }
+
+    #[test]
+    fn negative_indices_alternate_in_sign() {
+        let expected = [0, 1, -1, 2, -3, 5, -8, 13, -21];
+        for (n, expected) in expected.into_iter().enumerate() {
+            let n = -(n as i64);
+            assert_eq!(
+                BigInt::from(fibonacci_signed(n).unwrap()),
+                BigInt::from(expected),
+                "F({n})"
+            );
+        }
+
+        for n in [186, 187, 1000, 1001] {
+            let positive = BigInt::from(fibonacci_signed(n).unwrap());
+            let negative = BigInt::from(fibonacci_signed(-n).unwrap());
+            let sign = if n % 2 == 0 { -1 } else { 1 };
+            assert_eq!(negative, positive * sign, "F(-{n})");
+        }
+    }
+
+    #[test]
+    fn indices_past_the_limit_are_rejected() {
+        let limit = FibError::ResourceLimit {
+            limit: MAX_INDEX.into(),
+        };
+        assert_eq!(fibonacci_signed(i64::MIN), Err(limit.clone()));
+        assert_eq!(unsigned_index(MAX_INDEX as i64 + 1), Err(limit));
+        assert_eq!(unsigned_index(-1), Err(FibError::NegativeIndex { n: -1 }));
+    }
+
+    #[test]
+    fn big_path_matches_iterative_sum() {
+        let (mut a, mut b) = (BigUint::ZERO, BigUint::from(1u32));
+        for n in 0..2000 {
+            assert_eq!(fibonacci_big(n), a, "F({n})");
+            a += &b;
+            std::mem::swap(&mut a, &mut b);
+        }
+    }
+}
@@ -0,0 +1,196 @@
+//! Lucas sequences U_n(P, Q) and V_n(P, Q), which generalise the fibonacci recurrence:
+//!
+//! U_0 = 0, U_1 = 1, U_n = P * U_(n - 1) - Q * U_(n - 2)
+//! V_0 = 2, V_1 = P, V_n = P * V_(n - 1) - Q * V_(n - 2)
+//!
+//! The fibonacci numbers are U_n(1, -1) and the lucas numbers are V_n(1, -1).
+//! [`fibonacci`](crate::fibonacci) is kept as a specialised version of that case,
+//! as it can skip most of the work the general doubling has to do.
+
+use num_bigint::{BigInt, BigUint, Sign};
+
+use crate::error::FibError;
+use crate::modular::{add_mod, mul_mod, reduce, sub_mod};
+use crate::{MAX_INDEX, fibonacci_pair_big};
+
+/// A pure Rust function to compute `(U_n(P, Q), V_n(P, Q))` using doubling:
+///
+/// U_2k = U_k * V_k
+/// V_2k = V_k² - 2 * Q^k
+/// U_(k + 1) = (P * U_k + V_k) / 2
+/// V_(k + 1) = (D * U_k + P * V_k) / 2, where D = P² - 4Q
+///
+/// Both divisions are always exact.
+pub fn lucas_sequences(n: u32, p: &BigInt, q: &BigInt) -> (BigInt, BigInt) {
+    let d = p * p - 4 * q;
+
+    let (mut u, mut v, mut q_k) = (BigInt::ZERO, BigInt::from(2), BigInt::from(1));
+    for shift in (0..u32::BITS - n.leading_zeros()).rev() {
+        u *= &v;
+        v = &v * &v - 2 * &q_k;
+        q_k = &q_k * &q_k;
+
+        if n >> shift & 1 == 1 {
+            (u, v) = ((p * &u + &v) / 2, (&d * &u + p * &v) / 2);
+            q_k *= q;
+        }
+    }
+
+    (u, v)
+}
+
+/// A pure Rust function to compute U_n(P, Q)
+pub fn lucas_u(n: u32, p: &BigInt, q: &BigInt) -> BigInt {
+    lucas_sequences(n, p, q).0
+}
+
+/// A pure Rust function to compute V_n(P, Q)
+pub fn lucas_v(n: u32, p: &BigInt, q: &BigInt) -> BigInt {
+    lucas_sequences(n, p, q).1
+}
+
+/// A pure Rust function to compute the `n`th lucas number L(n) = V_n(1, -1)
+/// or a [`FibError::ResourceLimit`] if `|n|` is larger than [`MAX_INDEX`]
+///
+/// Negative indices are supported using L(-n) = (-1)^n * L(n)
+pub fn lucas(n: i64) -> Result<BigInt, FibError> {
+    let magnitude = u32::try_from(n.unsigned_abs()).map_err(|_| FibError::ResourceLimit {
+        limit: MAX_INDEX.into(),
+    })?;
+
+    // L(n) = F(n - 1) + F(n + 1) = 2 * F(n + 1) - F(n)
+    let (a, b) = fibonacci_pair_big(magnitude);
+    let result = BigInt::from((b << 1u8) - a);
+
+    if n < 0 && magnitude % 2 == 1 {
+        Ok(-result)
+    } else {
+        Ok(result)
+    }
+}
+
+/// A pure Rust function to compute `(U_n(P, Q) mod m, V_n(P, Q) mod m)`
+/// or an [`FibError::InvalidModulus`] if `m` is zero
+///
+/// The divisions by two of the exact doubling don't work for even `m`, so this raises
+/// the companion matrix [[P, -Q], [1, 0]] to the `n`th power instead, which is
+/// [[U_(n + 1), -Q * U_n], [U_n, -Q * U_(n - 1)]] and has V_n as its trace.
+pub fn lucas_sequences_mod(
+    n: &BigUint,
+    p: &BigInt,
+    q: &BigInt,
+    m: u128,
+) -> Result<(u128, u128), FibError> {
+    if m == 0 {
+        return Err(FibError::InvalidModulus);
+    }
+
+    type Matrix = [[u128; 2]; 2];
+    let multiply = |a: &Matrix, b: &Matrix| -> Matrix {
+        let entry = |i: usize, j: usize| {
+            add_mod(
+                mul_mod(a[i][0], b[0][j], m),
+                mul_mod(a[i][1], b[1][j], m),
+                m,
+            )
+        };
+        [[entry(0, 0), entry(0, 1)], [entry(1, 0), entry(1, 1)]]
+    };
+
+    let companion = [[reduce(p, m), sub_mod(0, reduce(q, m), m)], [1 % m, 0]];
+    let mut power = [[1 % m, 0], [0, 1 % m]];
+    for shift in (0..n.bits()).rev() {
+        power = multiply(&power, &power);
+        if n.bit(shift) {
+            power = multiply(&power, &companion);
+        }
+    }
+
+    Ok((power[1][0], add_mod(power[0][0], power[1][1], m)))
+}
+
+/// A pure Rust function to compute U_n(P, Q) mod m
+pub fn lucas_u_mod(n: &BigUint, p: &BigInt, q: &BigInt, m: u128) -> Result<u128, FibError> {
+    Ok(lucas_sequences_mod(n, p, q, m)?.0)
+}
+
+/// A pure Rust function to compute V_n(P, Q) mod m
+pub fn lucas_v_mod(n: &BigUint, p: &BigInt, q: &BigInt, m: u128) -> Result<u128, FibError> {
+    Ok(lucas_sequences_mod(n, p, q, m)?.1)
+}
+
+/// A pure Rust function to compute the `n`th lucas number modulo `m`,
+/// for positive and negative `n`
+pub fn lucas_mod(n: &BigInt, m: u128) -> Result<u128, FibError> {
+    let magnitude = n.magnitude();
+    let result = lucas_v_mod(magnitude, &BigInt::from(1), &BigInt::from(-1), m)?;
+
+    if n.sign() == Sign::Minus && magnitude.bit(0) {
+        Ok(sub_mod(0, result, m))
+    } else {
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci_big;
+
+    /// Walks the recurrence term by term
+    fn lucas_sequences_iterative(n: u32, p: i64, q: i64) -> (BigInt, BigInt) {
+        let (p, q) = (BigInt::from(p), BigInt::from(q));
+        let (mut u, mut u_next) = (BigInt::ZERO, BigInt::from(1));
+        let (mut v, mut v_next) = (BigInt::from(2), p.clone());
+
+        for _ in 0..n {
+            (u, u_next) = (u_next.clone(), &p * &u_next - &q * &u);
+            (v, v_next) = (v_next.clone(), &p * &v_next - &q * &v);
+        }
+
+        (u, v)
+    }
+
+    #[test]
+    fn matches_recurrence() {
+        for (p, q) in [(1, -1), (2, -1), (3, 2), (-4, 7), (0, 5), (6, 9)] {
+            for n in 0..100 {
+                let expected = lucas_sequences_iterative(n, p, q);
+                let (p, q) = (BigInt::from(p), BigInt::from(q));
+                assert_eq!(lucas_sequences(n, &p, &q), expected, "n = {n}");
+
+                for m in [1, 2, 12, 1_000_000_007, u128::MAX] {
+                    let reduced = (reduce(&expected.0, m), reduce(&expected.1, m));
+                    let n = BigUint::from(n);
+                    assert_eq!(
+                        lucas_sequences_mod(&n, &p, &q, m),
+                        Ok(reduced),
+                        "n = {n}, m = {m}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fibonacci_and_lucas_numbers_are_special_cases() {
+        let (p, q) = (BigInt::from(1), BigInt::from(-1));
+        for n in 0..500 {
+            assert_eq!(lucas_u(n, &p, &q), BigInt::from(fibonacci_big(n)));
+            assert_eq!(Ok(lucas_v(n, &p, &q)), lucas(n.into()));
+        }
+    }
+
+    #[test]
+    fn negative_lucas_numbers() {
+        let expected = [2, -1, 3, -4, 7, -11];
+        for (n, expected) in expected.into_iter().enumerate() {
+            let n = -(n as i64);
+            assert_eq!(lucas(n), Ok(BigInt::from(expected)));
+            assert_eq!(
+                lucas_mod(&BigInt::from(n), 5),
+                Ok(reduce(&BigInt::from(expected), 5))
+            );
+        }
+    }
+}
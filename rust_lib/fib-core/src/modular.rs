@@ -0,0 +1,289 @@
+//! Fibonacci numbers modulo `m`, for indices far beyond what [`fibonacci`](crate::fibonacci) can handle
+
+use num_bigint::{BigInt, BigUint, Sign};
+
+use crate::error::FibError;
+use crate::pisano::pisano_period;
+
+/// Reduces `x` into `0..m`, even if it is negative
+pub(crate) fn reduce(x: &BigInt, m: u128) -> u128 {
+    let m = BigInt::from(m);
+    let mut x = x % &m;
+    if x.sign() == Sign::Minus {
+        x += m;
+    }
+
+    u128::try_from(x).expect("x was reduced modulo a u128")
+}
+
+/// Computes `(a + b) mod m` without overflowing
+///
+/// `a` and `b` must already be reduced modulo `m`
+pub(crate) fn add_mod(a: u128, b: u128, m: u128) -> u128 {
+    let (sum, overflowed) = a.overflowing_add(b);
+    if overflowed || sum >= m {
+        sum.wrapping_sub(m)
+    } else {
+        sum
+    }
+}
+
+/// Computes `(a - b) mod m` without underflowing
+///
+/// `a` and `b` must already be reduced modulo `m`
+pub(crate) fn sub_mod(a: u128, b: u128, m: u128) -> u128 {
+    if a >= b { a - b } else { m - (b - a) }
+}
+
+/// Computes `(a * b) mod m` without overflowing
+///
+/// `a` and `b` must already be reduced modulo `m`
+pub(crate) fn mul_mod(a: u128, b: u128, m: u128) -> u128 {
+    if m <= u64::MAX as u128 {
+        // both factors are below 2^64, so the product can't overflow a u128
+        return a * b % m;
+    }
+
+    let (high, low) = widening_mul(a, b);
+    rem_wide(high, low, m)
+}
+
+const LOW_64: u128 = u64::MAX as u128;
+
+/// Computes the full 256 bit product `a * b` as its `(high, low)` halves
+fn widening_mul(a: u128, b: u128) -> (u128, u128) {
+    let (a_high, a_low) = (a >> 64, a & LOW_64);
+    let (b_high, b_low) = (b >> 64, b & LOW_64);
+
+    let low_low = a_low * b_low;
+    let low_high = a_low * b_high;
+    let high_low = a_high * b_low;
+    let high_high = a_high * b_high;
+
+    // the sum of three numbers below 2^64, which can't overflow
+    let middle = (low_low >> 64) + (low_high & LOW_64) + (high_low & LOW_64);
+    let low = (middle << 64) | (low_low & LOW_64);
+    let high = high_high + (low_high >> 64) + (high_low >> 64) + (middle >> 64);
+    (high, low)
+}
+
+/// Computes `(high * 2^128 + low) mod m` for `m` above 2^64, using Knuth's long division
+/// (TAOCP 4.3.1, algorithm D) on 64 bit digits
+///
+/// `high` must be smaller than `m`, which holds for the product of two reduced factors.
+fn rem_wide(high: u128, low: u128, m: u128) -> u128 {
+    // normalize the divisor so its top bit is set, which keeps the estimated quotient digits
+    // at most two too large. `m` has two digits, so the shift is below 64.
+    let shift = m.leading_zeros();
+    let divisor = m << shift;
+    let divisor = [divisor as u64, (divisor >> 64) as u64];
+
+    let high = (high << shift) | (low >> 1 >> (127 - shift));
+    let low = low << shift;
+    let mut digits = [
+        low as u64,
+        (low >> 64) as u64,
+        high as u64,
+        (high >> 64) as u64,
+        0,
+    ];
+
+    for j in (0..3).rev() {
+        // estimate the quotient digit from the top two digits and correct it with the third
+        let top = (u128::from(digits[j + 2]) << 64) | u128::from(digits[j + 1]);
+        let mut quotient = top / u128::from(divisor[1]);
+        let mut remainder = top % u128::from(divisor[1]);
+        while quotient > LOW_64
+            || quotient * u128::from(divisor[0]) > (remainder << 64 | u128::from(digits[j]))
+        {
+            quotient -= 1;
+            remainder += u128::from(divisor[1]);
+            if remainder > LOW_64 {
+                break;
+            }
+        }
+
+        // subtract `quotient * divisor` from the current digits
+        let mut borrow = 0i128;
+        for i in 0..2 {
+            let product = quotient * u128::from(divisor[i]);
+            let difference = i128::from(digits[i + j]) - borrow - (product & LOW_64) as i128;
+            digits[i + j] = difference as u64;
+            borrow = (product >> 64) as i128 - (difference >> 64);
+        }
+        let difference = i128::from(digits[j + 2]) - borrow;
+        digits[j + 2] = difference as u64;
+
+        // the estimate was still one too large, so add the divisor back
+        if difference < 0 {
+            let mut carry = 0;
+            for i in 0..2 {
+                let sum = u128::from(digits[i + j]) + u128::from(divisor[i]) + carry;
+                digits[i + j] = sum as u64;
+                carry = sum >> 64;
+            }
+            digits[j + 2] = digits[j + 2].wrapping_add(carry as u64);
+        }
+    }
+
+    // the remainder is left in the lowest two digits, still shifted by the normalization
+    ((u128::from(digits[1]) << 64) | u128::from(digits[0])) >> shift
+}
+
+/// Above this many bits it is cheaper to reduce the index by the pisano period first
+const PISANO_REDUCTION_BITS: u64 = 1024;
+
+/// A pure Rust function to compute the `n`th fibonacci number modulo `m`
+/// or an [`FibError::InvalidModulus`] if `m` is zero
+///
+/// This uses the same fast doubling as [`fibonacci`](crate::fibonacci),
+/// so it only needs `O(log n)` modular multiplications.
+/// Negative indices are supported using F(-n) = (-1)^(n + 1) * F(n).
+pub fn fibonacci_mod(n: &BigInt, m: u128) -> Result<u128, FibError> {
+    if m == 0 {
+        return Err(FibError::InvalidModulus);
+    }
+
+    let magnitude = n.magnitude();
+
+    // the sequence repeats every π(m) terms, so huge indices can be reduced first
+    let result = if let Ok(small_m) = u64::try_from(m)
+        && magnitude.bits() > PISANO_REDUCTION_BITS
+    {
+        let period = pisano_period(small_m)?;
+        fibonacci_pair_mod(&(magnitude % period), m).0
+    } else {
+        fibonacci_pair_mod(magnitude, m).0
+    };
+
+    if n.sign() == Sign::Minus && !magnitude.bit(0) {
+        Ok(sub_mod(0, result, m))
+    } else {
+        Ok(result)
+    }
+}
+
+/// Computes `(F(n) mod m, F(n + 1) mod m)` by fast doubling
+///
+/// `m` must not be zero
+pub(crate) fn fibonacci_pair_mod(n: &BigUint, m: u128) -> (u128, u128) {
+    let (mut a, mut b) = (0, 1 % m);
+    for shift in (0..n.bits()).rev() {
+        let even = mul_mod(a, sub_mod(add_mod(b, b, m), a, m), m);
+        let odd = add_mod(mul_mod(a, a, m), mul_mod(b, b, m), m);
+
+        (a, b) = if n.bit(shift) {
+            (odd, add_mod(even, odd, m))
+        } else {
+            (even, odd)
+        };
+    }
+
+    (a, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::fibonacci_big;
+
+    #[test]
+    fn matches_reduced_big_fibonacci() {
+        let moduli = [
+            1,
+            2,
+            10,
+            1_000_000_007,
+            u64::MAX as u128,
+            u64::MAX as u128 + 2,
+            u128::MAX,
+        ];
+        for m in moduli {
+            for n in (0..3000).step_by(7) {
+                let expected = fibonacci_big(n) % m;
+                assert_eq!(
+                    fibonacci_mod(&BigInt::from(n), m).map(BigUint::from),
+                    Ok(expected),
+                    "F({n}) mod {m}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn huge_indices_are_reduced_by_the_period() {
+        let n = BigInt::from(1) << 5000u32;
+        for m in [1u64, 2, 10, 1_000_000_007, u64::MAX] {
+            let m = m as u128;
+            assert_eq!(
+                fibonacci_mod(&n, m),
+                Ok(fibonacci_pair_mod(n.magnitude(), m).0),
+                "mod {m}"
+            );
+        }
+    }
+
+    #[test]
+    fn negative_indices() {
+        for n in 1..500 {
+            let positive = fibonacci_mod(&BigInt::from(n), 1000).unwrap();
+            let negative = fibonacci_mod(&BigInt::from(-n), 1000).unwrap();
+            let expected = if n % 2 == 0 {
+                (1000 - positive) % 1000
+            } else {
+                positive
+            };
+            assert_eq!(negative, expected, "F(-{n}) mod 1000");
+        }
+    }
+
+    #[test]
+    fn zero_modulus_is_rejected() {
+        assert_eq!(
+            fibonacci_mod(&BigInt::from(10), 0),
+            Err(FibError::InvalidModulus)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn mul_mod_matches_big_integers(a: u128, b: u128, m in (u64::MAX as u128 + 1)..=u128::MAX) {
+            let (a, b) = (a % m, b % m);
+            let expected = BigUint::from(a) * BigUint::from(b) % m;
+            prop_assert_eq!(BigUint::from(mul_mod(a, b, m)), expected);
+        }
+    }
+
+    #[test]
+    fn mul_mod_edge_cases() {
+        let moduli = [
+            u64::MAX as u128 + 1,
+            u64::MAX as u128 + 2,
+            1 << 127,
+            (1 << 127) + 1,
+            u128::MAX - 1,
+            u128::MAX,
+        ];
+        for m in moduli {
+            for a in [0, 1, 2, m / 2, m - 2, m - 1] {
+                for b in [0, 1, m / 3, m - 1] {
+                    let expected = BigUint::from(a) * BigUint::from(b) % m;
+                    assert_eq!(
+                        BigUint::from(mul_mod(a, b, m)),
+                        expected,
+                        "{a} * {b} mod {m}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mul_mod_does_not_overflow() {
+        let m = u128::MAX - 158; // the largest prime below 2^128
+        let product = BigUint::from(m - 1) * BigUint::from(m - 2) % m;
+        assert_eq!(BigUint::from(mul_mod(m - 1, m - 2, m)), product);
+    }
+}
@@ -0,0 +1,164 @@
+//! Pisano periods: the period with which the fibonacci numbers repeat modulo `m`
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+use num_bigint::BigUint;
+
+use crate::error::FibError;
+use crate::factor::factorize;
+use crate::modular::fibonacci_pair_mod;
+
+/// Periods which have already been computed, keyed by their modulus
+static PISANO_CACHE: LazyLock<Mutex<HashMap<u64, u128>>> = LazyLock::new(Default::default);
+
+/// A pure Rust function to compute the pisano period π(m)
+/// or an [`FibError::InvalidModulus`] if `m` is zero
+///
+/// `m` is factored into prime powers, whose periods are combined with their lcm.
+/// Results are cached, so repeated queries for the same modulus are cheap.
+pub fn pisano_period(m: u64) -> Result<u128, FibError> {
+    if m == 0 {
+        return Err(FibError::InvalidModulus);
+    }
+
+    if let Some(&period) = PISANO_CACHE.lock().unwrap().get(&m) {
+        return Ok(period);
+    }
+
+    let period = factorize(m)
+        .into_iter()
+        .map(|(p, exponent)| prime_power_period(p, exponent))
+        .fold(1, lcm);
+
+    PISANO_CACHE.lock().unwrap().insert(m, period);
+    Ok(period)
+}
+
+/// Forgets all cached pisano periods
+pub fn clear_pisano_cache() {
+    PISANO_CACHE.lock().unwrap().clear();
+}
+
+/// Computes π(p^k), which is p^(k - 1) * π(p) for every prime anyone has checked so far.
+/// We still verify this, to also be correct for Wall-Sun-Sun primes should they exist.
+fn prime_power_period(p: u64, exponent: u32) -> u128 {
+    let prime_period = shortest_period(
+        p as u128,
+        match p {
+            2 => 3,
+            5 => 20,
+            // p ≡ ±1 (mod 10): π(p) divides p - 1
+            _ if matches!(p % 10, 1 | 9) => p as u128 - 1,
+            // p ≡ ±3 (mod 10): π(p) divides 2(p + 1)
+            _ => 2 * (p as u128 + 1),
+        },
+    );
+
+    let modulus = (p as u128).pow(exponent);
+    let mut period = prime_period;
+    for _ in 1..exponent {
+        period *= p as u128;
+    }
+
+    // the period can only be shorter by factors of p
+    while period.is_multiple_of(prime_period * p as u128) && is_period(period / p as u128, modulus)
+    {
+        period /= p as u128;
+    }
+
+    period
+}
+
+/// Finds the smallest period modulo the prime `p` which divides `multiple`
+fn shortest_period(p: u128, multiple: u128) -> u128 {
+    let mut period = multiple;
+
+    // `multiple` is at most 2(p + 1), so its odd part always fits into a u64
+    let factors = factorize((multiple >> multiple.trailing_zeros()) as u64)
+        .into_iter()
+        .map(|(q, _)| q as u128)
+        .chain(multiple.is_multiple_of(2).then_some(2));
+
+    for q in factors {
+        while period.is_multiple_of(q) && is_period(period / q, p) {
+            period /= q;
+        }
+    }
+
+    period
+}
+
+/// Checks if the fibonacci numbers modulo `m` repeat after `period` terms
+fn is_period(period: u128, m: u128) -> bool {
+    fibonacci_pair_mod(&BigUint::from(period), m) == (0, 1 % m)
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
+
+fn lcm(a: u128, b: u128) -> u128 {
+    a / gcd(a, b) * b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Finds the period by walking the sequence until it returns to (0, 1)
+    fn pisano_period_brute_force(m: u64) -> u128 {
+        let (mut a, mut b) = (0, 1 % m);
+        for period in 1.. {
+            (a, b) = (b, (a + b) % m);
+            if (a, b) == (0, 1 % m) {
+                return period;
+            }
+        }
+
+        unreachable!()
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        for m in 1..2000 {
+            assert_eq!(pisano_period(m), Ok(pisano_period_brute_force(m)), "π({m})");
+        }
+    }
+
+    #[test]
+    fn large_moduli() {
+        // 10^9 + 7 ≡ 7 (mod 10), so its period has to divide 2 * (10^9 + 8)
+        let period = pisano_period(1_000_000_007).unwrap();
+        assert_eq!(2 * (1_000_000_008) % period, 0);
+        assert!(is_period(period, 1_000_000_007));
+
+        let period = pisano_period(u64::MAX).unwrap();
+        assert!(is_period(period, u64::MAX as u128));
+    }
+
+    #[test]
+    fn cache_is_shared_between_threads() {
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for m in 1..500 {
+                        assert_eq!(pisano_period(m), Ok(pisano_period_brute_force(m)));
+                        if m % 100 == 0 {
+                            clear_pisano_cache();
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn zero_has_no_period() {
+        assert_eq!(pisano_period(0), Err(FibError::InvalidModulus));
+    }
+}
@@ -0,0 +1,136 @@
+//! Computing whole ranges of fibonacci numbers, optionally in parallel
+
+use std::num::NonZeroUsize;
+#[cfg(feature = "rayon")]
+use std::sync::{Arc, Mutex};
+
+use crate::FibonacciNumber;
+use crate::iter::FibonacciIter;
+
+/// Chunks smaller than this aren't worth the cost of seeding them by fast doubling
+#[cfg(feature = "rayon")]
+const MIN_CHUNK_LEN: u32 = 256;
+
+/// The pool of the last call with an explicit number of threads.
+/// Spawning the threads of a new pool on every call would cost more than small ranges take.
+#[cfg(feature = "rayon")]
+static POOL: PoolCache = PoolCache(Mutex::new(None));
+
+/// A pure Rust function to compute `F(start), F(start + 1), ..., F(stop - 1)`
+///
+/// The range is split into chunks, which each start by fast doubling and then
+/// iterate linearly. With the `rayon` feature the chunks are computed in parallel,
+/// on `threads` threads or rayon's global pool if that is None.
+///
+/// The pool for `threads` is kept for the next call, only changing the number of threads spawns a new one.
+pub fn fibonacci_range(
+    start: u32,
+    stop: u32,
+    threads: Option<NonZeroUsize>,
+) -> Vec<FibonacciNumber> {
+    if start >= stop {
+        return Vec::new();
+    }
+
+    #[cfg(feature = "rayon")]
+    {
+        let workers = threads.map_or_else(rayon::current_num_threads, NonZeroUsize::get);
+        let compute = || parallel_range(start, stop, workers);
+
+        match threads.map(|threads| POOL.get(threads)) {
+            Some(Ok(pool)) => pool.install(compute),
+            // if we can't spawn a dedicated pool, the global one will have to do
+            Some(Err(_)) | None => compute(),
+        }
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    {
+        let _ = threads;
+        FibonacciIter::new(start, Some(stop), 1).collect()
+    }
+}
+
+/// Holds a single thread pool, keyed by its number of threads
+#[cfg(feature = "rayon")]
+struct PoolCache(Mutex<Option<(NonZeroUsize, Arc<rayon::ThreadPool>)>>);
+
+#[cfg(feature = "rayon")]
+impl PoolCache {
+    /// Returns the cached pool if it has `threads` threads, or replaces it with a new one
+    fn get(
+        &self,
+        threads: NonZeroUsize,
+    ) -> Result<Arc<rayon::ThreadPool>, rayon::ThreadPoolBuildError> {
+        let mut cached = self.0.lock().unwrap();
+        if let Some((cached_threads, pool)) = &*cached
+            && *cached_threads == threads
+        {
+            return Ok(pool.clone());
+        }
+
+        // the previous pool shuts down once the calls still using it are done
+        let pool = Arc::new(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads.get())
+                .build()?,
+        );
+        *cached = Some((threads, pool.clone()));
+        Ok(pool)
+    }
+}
+
+#[cfg(feature = "rayon")]
+fn parallel_range(start: u32, stop: u32, workers: usize) -> Vec<FibonacciNumber> {
+    use rayon::prelude::*;
+
+    // a few chunks per worker, so uneven chunks (big numbers at the end) still balance out
+    let chunk_count = u32::try_from(workers.saturating_mul(4)).unwrap_or(u32::MAX);
+    let chunk_len = ((stop - start) / chunk_count).max(MIN_CHUNK_LEN);
+
+    let chunks = (start..stop)
+        .step_by(chunk_len as usize)
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|chunk_start| {
+            let chunk_stop = chunk_start.saturating_add(chunk_len).min(stop);
+            FibonacciIter::new(chunk_start, Some(chunk_stop), 1).collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    chunks.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci_number;
+
+    #[test]
+    fn matches_single_values() {
+        for threads in [None, NonZeroUsize::new(1), NonZeroUsize::new(3)] {
+            let range = fibonacci_range(10, 5000, threads);
+            assert!(range.into_iter().eq((10..5000).map(fibonacci_number)));
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn thread_pools_are_reused() {
+        let cache = PoolCache(Mutex::new(None));
+        let three = NonZeroUsize::new(3).unwrap();
+        let pool = cache.get(three).unwrap();
+        assert_eq!(pool.current_num_threads(), 3);
+        assert!(Arc::ptr_eq(&pool, &cache.get(three).unwrap()));
+
+        let four = cache.get(NonZeroUsize::new(4).unwrap()).unwrap();
+        assert_eq!(four.current_num_threads(), 4);
+        assert!(!Arc::ptr_eq(&pool, &four));
+    }
+
+    #[test]
+    fn empty_ranges() {
+        assert!(fibonacci_range(5, 5, None).is_empty());
+        assert!(fibonacci_range(6, 5, None).is_empty());
+    }
+}
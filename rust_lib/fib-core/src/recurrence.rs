@@ -0,0 +1,388 @@
+//! Arbitrary linear recurrences a_n = c_1 * a_(n - 1) + ... + c_k * a_(n - k),
+//! like the tribonacci numbers or the padovan sequence
+
+use std::collections::VecDeque;
+
+use num_bigint::{BigInt, BigUint};
+
+use crate::error::FibError;
+use crate::modular::{add_mod, mul_mod, reduce};
+
+/// A linear recurrence of order `k`, given by its coefficients `c_1, ..., c_k`
+/// and its initial terms `a_0, ..., a_(k - 1)`, optionally over the integers modulo `m`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinearRecurrence {
+    coefficients: Vec<BigInt>,
+    initial: Vec<BigInt>,
+    modulus: Option<u128>,
+}
+
+impl LinearRecurrence {
+    /// Creates a recurrence from its coefficients and initial terms
+    ///
+    /// There has to be at least one coefficient and exactly as many initial terms as coefficients
+    pub fn new(coefficients: Vec<BigInt>, initial: Vec<BigInt>) -> Self {
+        assert!(
+            !coefficients.is_empty(),
+            "A recurrence needs at least one coefficient"
+        );
+        assert_eq!(
+            coefficients.len(),
+            initial.len(),
+            "A recurrence needs as many initial terms as coefficients"
+        );
+
+        Self {
+            coefficients,
+            initial,
+            modulus: None,
+        }
+    }
+
+    /// Turns this into a recurrence over the integers modulo `m`,
+    /// or returns an [`FibError::InvalidModulus`] if `m` is zero
+    pub fn with_modulus(self, m: u128) -> Result<Self, FibError> {
+        if m == 0 {
+            return Err(FibError::InvalidModulus);
+        }
+
+        let reduce =
+            |values: Vec<BigInt>| values.iter().map(|x| BigInt::from(reduce(x, m))).collect();
+
+        Ok(Self {
+            coefficients: reduce(self.coefficients),
+            initial: reduce(self.initial),
+            modulus: Some(m),
+        })
+    }
+
+    pub fn coefficients(&self) -> &[BigInt] {
+        &self.coefficients
+    }
+
+    pub fn initial(&self) -> &[BigInt] {
+        &self.initial
+    }
+
+    /// The modulus all terms are reduced by, if there is one
+    pub fn modulus(&self) -> Option<u128> {
+        self.modulus
+    }
+
+    /// The number of previous terms each term depends on
+    pub fn order(&self) -> usize {
+        self.coefficients.len()
+    }
+
+    /// Computes a_n using Kitamasa's method in `O(k² log n)` multiplications
+    pub fn nth(&self, n: u32) -> BigInt {
+        let n = BigUint::from(n);
+        match self.modulus {
+            Some(m) => BigInt::from(self.nth_mod(&n, m).expect("the modulus is not zero")),
+            None => kitamasa(&Exact, &self.coefficients, &self.initial, &n),
+        }
+    }
+
+    /// Computes a_n mod m or an [`FibError::InvalidModulus`] if `m` is zero
+    ///
+    /// A recurrence with a modulus can only be computed modulo that one,
+    /// other moduli return a [`FibError::ModulusMismatch`].
+    pub fn nth_mod(&self, n: &BigUint, m: u128) -> Result<u128, FibError> {
+        if m == 0 {
+            return Err(FibError::InvalidModulus);
+        }
+        if let Some(modulus) = self.modulus.filter(|&modulus| modulus != m) {
+            return Err(FibError::ModulusMismatch { m, modulus });
+        }
+
+        let reduce = |values: &[BigInt]| values.iter().map(|x| reduce(x, m)).collect::<Vec<_>>();
+        let (coefficients, initial) = (reduce(&self.coefficients), reduce(&self.initial));
+
+        Ok(kitamasa(&Modular(m), &coefficients, &initial, n))
+    }
+
+    /// Lazily yields a_0, a_1, ...
+    pub fn iter(&self) -> LinearRecurrenceIter {
+        LinearRecurrenceIter::new(self)
+    }
+}
+
+/// The ring Kitamasa's method computes in
+trait Ring {
+    type Value: Clone;
+
+    fn zero(&self) -> Self::Value;
+    fn one(&self) -> Self::Value;
+    fn add(&self, a: &Self::Value, b: &Self::Value) -> Self::Value;
+    fn mul(&self, a: &Self::Value, b: &Self::Value) -> Self::Value;
+}
+
+/// Exact integer arithmetic
+struct Exact;
+
+impl Ring for Exact {
+    type Value = BigInt;
+
+    fn zero(&self) -> BigInt {
+        BigInt::ZERO
+    }
+    fn one(&self) -> BigInt {
+        BigInt::from(1)
+    }
+    fn add(&self, a: &BigInt, b: &BigInt) -> BigInt {
+        a + b
+    }
+    fn mul(&self, a: &BigInt, b: &BigInt) -> BigInt {
+        a * b
+    }
+}
+
+/// Arithmetic modulo a non-zero modulus
+struct Modular(u128);
+
+impl Ring for Modular {
+    type Value = u128;
+
+    fn zero(&self) -> u128 {
+        0
+    }
+    fn one(&self) -> u128 {
+        1 % self.0
+    }
+    fn add(&self, a: &u128, b: &u128) -> u128 {
+        add_mod(*a, *b, self.0)
+    }
+    fn mul(&self, a: &u128, b: &u128) -> u128 {
+        mul_mod(*a, *b, self.0)
+    }
+}
+
+/// Computes a_n by reducing x^n modulo the characteristic polynomial
+/// x^k - c_1 * x^(k - 1) - ... - c_k, which leaves a polynomial of degree
+/// below `k` whose coefficients are the weights of a_0, ..., a_(k - 1)
+fn kitamasa<R: Ring>(
+    ring: &R,
+    coefficients: &[R::Value],
+    initial: &[R::Value],
+    n: &BigUint,
+) -> R::Value {
+    let k = coefficients.len();
+
+    // replaces every x^d with d >= k by x^(d - k) * (c_1 * x^(k - 1) + ... + c_k)
+    let reduce = |mut polynomial: Vec<R::Value>| {
+        for degree in (k..polynomial.len()).rev() {
+            let top = std::mem::replace(&mut polynomial[degree], ring.zero());
+            for (i, coefficient) in coefficients.iter().enumerate() {
+                let target = degree - 1 - i;
+                polynomial[target] = ring.add(&polynomial[target], &ring.mul(&top, coefficient));
+            }
+        }
+        polynomial.truncate(k);
+        polynomial
+    };
+
+    let mut power = vec![ring.zero(); k];
+    power[0] = ring.one();
+    for shift in (0..n.bits()).rev() {
+        let mut square = vec![ring.zero(); 2 * k - 1];
+        for (i, a) in power.iter().enumerate() {
+            for (j, b) in power.iter().enumerate() {
+                square[i + j] = ring.add(&square[i + j], &ring.mul(a, b));
+            }
+        }
+        power = reduce(square);
+
+        if n.bit(shift) {
+            power.insert(0, ring.zero());
+            power = reduce(power);
+        }
+    }
+
+    power
+        .iter()
+        .zip(initial)
+        .fold(ring.zero(), |sum, (weight, term)| {
+            ring.add(&sum, &ring.mul(weight, term))
+        })
+}
+
+/// The last `k` terms of a recurrence, which are only stored as [`BigInt`]s once they no longer fit into i128s
+#[derive(Debug, Clone)]
+enum Window {
+    Small(VecDeque<i128>, Vec<i128>),
+    Big(VecDeque<BigInt>),
+    /// A recurrence modulo the last field, whose terms never get large
+    Modular(VecDeque<u128>, Vec<u128>, u128),
+}
+
+/// A lazy iterator over the terms of a [`LinearRecurrence`]
+#[derive(Debug, Clone)]
+pub struct LinearRecurrenceIter {
+    coefficients: Vec<BigInt>,
+    window: Window,
+}
+
+impl LinearRecurrenceIter {
+    fn new(recurrence: &LinearRecurrence) -> Self {
+        let small = |values: &[BigInt]| {
+            values
+                .iter()
+                .map(i128::try_from)
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        let reduced =
+            |values: &[BigInt], m| values.iter().map(|x| reduce(x, m)).collect::<Vec<_>>();
+
+        let window = if let Some(m) = recurrence.modulus {
+            Window::Modular(
+                reduced(&recurrence.initial, m).into(),
+                reduced(&recurrence.coefficients, m),
+                m,
+            )
+        } else {
+            match (small(&recurrence.initial), small(&recurrence.coefficients)) {
+                (Ok(initial), Ok(coefficients)) => Window::Small(initial.into(), coefficients),
+                _ => Window::Big(recurrence.initial.iter().cloned().collect()),
+            }
+        };
+
+        Self {
+            coefficients: recurrence.coefficients.clone(),
+            window,
+        }
+    }
+}
+
+impl Iterator for LinearRecurrenceIter {
+    type Item = BigInt;
+
+    fn next(&mut self) -> Option<BigInt> {
+        if let Window::Modular(window, coefficients, m) = &mut self.window {
+            let m = *m;
+            let next = coefficients
+                .iter()
+                .zip(window.iter().rev())
+                .fold(0, |sum, (c, a)| add_mod(sum, mul_mod(*c, *a, m), m));
+
+            window.push_back(next);
+            return window.pop_front().map(BigInt::from);
+        }
+
+        if let Window::Small(window, coefficients) = &mut self.window {
+            let next = coefficients
+                .iter()
+                .zip(window.iter().rev())
+                .try_fold(0i128, |sum, (c, a)| sum.checked_add(c.checked_mul(*a)?));
+
+            match next {
+                Some(next) => {
+                    window.push_back(next);
+                    return window.pop_front().map(BigInt::from);
+                }
+                // the next term doesn't fit, so we continue with `BigInt`s
+                None => {
+                    self.window = Window::Big(window.iter().copied().map(BigInt::from).collect());
+                }
+            }
+        }
+
+        let Window::Big(window) = &mut self.window else {
+            unreachable!("small and modular windows return early")
+        };
+
+        let next = self
+            .coefficients
+            .iter()
+            .zip(window.iter().rev())
+            .map(|(c, a)| c * a)
+            .sum();
+        window.push_back(next);
+        window.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibonacci_big;
+
+    fn recurrence(coefficients: &[i64], initial: &[i64]) -> LinearRecurrence {
+        LinearRecurrence::new(
+            coefficients.iter().copied().map(BigInt::from).collect(),
+            initial.iter().copied().map(BigInt::from).collect(),
+        )
+    }
+
+    #[test]
+    fn fibonacci() {
+        let fibonacci = recurrence(&[1, 1], &[0, 1]);
+        for (n, term) in fibonacci.iter().take(500).enumerate() {
+            let expected = BigInt::from(fibonacci_big(n as u32));
+            assert_eq!(term, expected);
+            assert_eq!(fibonacci.nth(n as u32), expected);
+        }
+    }
+
+    #[test]
+    fn nth_matches_iteration() {
+        let recurrences = [
+            // tribonacci
+            recurrence(&[1, 1, 1], &[0, 0, 1]),
+            // padovan
+            recurrence(&[0, 1, 1], &[1, 1, 1]),
+            recurrence(&[3], &[-2]),
+            recurrence(&[2, -5, 0, 7], &[1, -1, 4, 0]),
+        ];
+
+        for recurrence in recurrences {
+            for (n, term) in recurrence.iter().take(400).enumerate() {
+                assert_eq!(recurrence.nth(n as u32), term, "{recurrence:?} at {n}");
+
+                for m in [1, 7, 1 << 64, u128::MAX] {
+                    let n = BigUint::from(n);
+                    assert_eq!(recurrence.nth_mod(&n, m), Ok(reduce(&term, m)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn recurrences_with_a_modulus() {
+        let tribonacci = recurrence(&[1, 1, 1], &[0, 0, 1]);
+        let reduced = tribonacci.clone().with_modulus(1000).unwrap();
+
+        for (n, (term, reduced_term)) in tribonacci.iter().zip(reduced.iter()).take(300).enumerate()
+        {
+            let expected = BigInt::from(reduce(&term, 1000));
+            assert_eq!(reduced_term, expected);
+            assert_eq!(reduced.nth(n as u32), expected);
+            assert_eq!(
+                reduced.nth_mod(&BigUint::from(n), 1000),
+                Ok(reduce(&term, 1000))
+            );
+        }
+
+        // the terms mod 1000 say nothing about the terms mod 7
+        assert_eq!(
+            reduced.nth_mod(&BigUint::from(5u32), 7),
+            Err(FibError::ModulusMismatch {
+                m: 7,
+                modulus: 1000
+            })
+        );
+    }
+
+    #[test]
+    fn zero_modulus_is_rejected() {
+        let fibonacci = recurrence(&[1, 1], &[0, 1]);
+        assert_eq!(
+            fibonacci.clone().with_modulus(0),
+            Err(FibError::InvalidModulus)
+        );
+        assert_eq!(
+            fibonacci.nth_mod(&BigUint::from(5u32), 0),
+            Err(FibError::InvalidModulus)
+        );
+    }
+}
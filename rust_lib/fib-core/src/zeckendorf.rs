@@ -0,0 +1,234 @@
+//! Zeckendorf representations: every non-negative integer is a unique sum of
+//! non-consecutive fibonacci numbers F(i) with i >= 2
+
+use num_bigint::BigUint;
+
+use crate::error::FibError;
+use crate::inverse::estimate_index;
+use crate::{FIB_U128, MAX_INDEX, fibonacci_pair_big};
+
+/// A pure Rust function to compute the Zeckendorf representation of `n`,
+/// as the indices of its fibonacci numbers from largest to smallest
+///
+/// Fails with a [`FibError::ResourceLimit`] if `n` is at least F([`MAX_INDEX`]),
+/// so the indices are always below [`MAX_INDEX`] and every F(index + 1) can be computed as well.
+pub fn zeckendorf(n: &BigUint) -> Result<Vec<u32>, FibError> {
+    match u128::try_from(n) {
+        Ok(n) => Ok(zeckendorf_small(n)),
+        Err(_) => zeckendorf_big(n),
+    }
+}
+
+/// Greedily subtracts the largest fibonacci number from the table which still fits
+fn zeckendorf_small(mut n: u128) -> Vec<u32> {
+    let mut indices = Vec::new();
+    let mut end = FIB_U128.len();
+
+    while n > 0 {
+        // F(1) = F(2), so we never pick index 1
+        let index = FIB_U128[2..end].partition_point(|&f| f <= n) + 1;
+        n -= FIB_U128[index];
+        indices.push(index as u32);
+        // the next fibonacci number is smaller than F(index - 1), so they are never consecutive
+        end = index - 1;
+    }
+
+    indices
+}
+
+/// Greedily subtracts fibonacci numbers while walking the sequence down from the largest one below `n`,
+/// so only two of them are kept in memory at a time
+fn zeckendorf_big(n: &BigUint) -> Result<Vec<u32>, FibError> {
+    let too_large = FibError::ResourceLimit {
+        limit: (MAX_INDEX - 1).into(),
+    };
+    let estimate = estimate_index(n).floor();
+    if estimate >= f64::from(MAX_INDEX) {
+        return Err(too_large);
+    }
+
+    // (a, b) = (F(index), F(index + 1)), correct the estimate until F(index) <= n < F(index + 1)
+    let mut index = estimate as u32;
+    let (mut a, mut b) = fibonacci_pair_big(index);
+    while a > *n {
+        b -= &a;
+        std::mem::swap(&mut a, &mut b);
+        index -= 1;
+    }
+    while b <= *n {
+        a += &b;
+        std::mem::swap(&mut a, &mut b);
+        index += 1;
+        if index > MAX_INDEX - 1 {
+            return Err(too_large);
+        }
+    }
+
+    let mut remaining = n.clone();
+    let mut indices = Vec::new();
+    while remaining > BigUint::ZERO {
+        // `remaining` < F(index + 1), so after subtracting F(index) it is smaller than F(index - 1)
+        // and the greedy choice never takes consecutive indices
+        if a <= remaining {
+            remaining -= &a;
+            indices.push(index);
+        }
+        b -= &a;
+        std::mem::swap(&mut a, &mut b);
+        index -= 1;
+    }
+
+    Ok(indices)
+}
+
+/// A pure Rust function to sum up the fibonacci numbers at `indices`,
+/// or None if they are not a valid Zeckendorf representation
+///
+/// The indices can be in any order, but must all be at least 2 and not be consecutive.
+/// Fails with a [`FibError::ResourceLimit`] for an index of [`MAX_INDEX`], which [`zeckendorf`] never returns.
+pub fn from_zeckendorf(indices: &[u32]) -> Result<Option<BigUint>, FibError> {
+    let mut indices = indices.to_vec();
+    indices.sort_unstable();
+
+    let valid = indices.first().is_none_or(|&first| first >= 2)
+        && indices.windows(2).all(|pair| pair[1] - pair[0] >= 2);
+    if !valid {
+        return Ok(None);
+    }
+
+    let Some(&largest) = indices.last() else {
+        return Ok(Some(BigUint::ZERO));
+    };
+
+    if largest > MAX_INDEX - 1 {
+        return Err(FibError::ResourceLimit {
+            limit: (MAX_INDEX - 1).into(),
+        });
+    }
+
+    if (largest as usize) < FIB_U128.len() {
+        let table = indices
+            .iter()
+            .map(|&index| BigUint::from(FIB_U128[index as usize]));
+        return Ok(Some(table.sum()));
+    }
+
+    // walk the sequence down once instead of computing every fibonacci number from scratch
+    let (mut a, mut b) = fibonacci_pair_big(largest);
+    let mut sum = BigUint::ZERO;
+    let mut index = largest;
+    for &wanted in indices.iter().rev() {
+        while index > wanted {
+            b -= &a;
+            std::mem::swap(&mut a, &mut b);
+            index -= 1;
+        }
+        sum += &a;
+    }
+
+    Ok(Some(sum))
+}
+
+/// Turns Zeckendorf indices into a bitmask, where bit `i - 2` is set for every F(i)
+pub fn to_bitmask(indices: &[u32]) -> BigUint {
+    let mut bitmask = BigUint::ZERO;
+    for &index in indices {
+        bitmask.set_bit((index - 2).into(), true);
+    }
+
+    bitmask
+}
+
+/// Turns a bitmask back into Zeckendorf indices, from largest to smallest,
+/// or a [`FibError::ResourceLimit`] if a bit stands for the index [`MAX_INDEX`] or above
+pub fn from_bitmask(bitmask: &BigUint) -> Result<Vec<u32>, FibError> {
+    // bit `MAX_INDEX - 2` is the first one out of range
+    if bitmask.bits() > u64::from(MAX_INDEX - 2) {
+        return Err(FibError::ResourceLimit {
+            limit: (MAX_INDEX - 1).into(),
+        });
+    }
+
+    Ok((0..bitmask.bits())
+        .rev()
+        .filter(|&bit| bitmask.bit(bit))
+        .map(|bit| bit as u32 + 2)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrips() {
+        for n in (0u32..5000).chain([u32::MAX]) {
+            let n = BigUint::from(n);
+            let indices = zeckendorf(&n).unwrap();
+            assert_eq!(
+                from_zeckendorf(&indices),
+                Ok(Some(n.clone())),
+                "{indices:?}"
+            );
+            assert_eq!(from_bitmask(&to_bitmask(&indices)), Ok(indices));
+        }
+
+        for bits in [127, 128, 129, 1000] {
+            for n in [
+                BigUint::from(1u32) << bits,
+                (BigUint::from(1u32) << bits) - 1u32,
+            ] {
+                let indices = zeckendorf(&n).unwrap();
+                assert_eq!(from_zeckendorf(&indices), Ok(Some(n)));
+            }
+        }
+
+        // the estimated index is right at the fibonacci numbers themselves and their neighbours
+        for index in [187, 1000, 10_001] {
+            let f = crate::fibonacci_big(index);
+            assert_eq!(zeckendorf(&f), Ok(vec![index]));
+            assert_eq!(zeckendorf(&(&f - 1u32)).unwrap()[0], index - 1);
+            assert_eq!(zeckendorf(&(&f + 1u32)), Ok(vec![index, 2]));
+        }
+    }
+
+    #[test]
+    fn roundtrips_large_inputs() {
+        // only two fibonacci numbers are kept at a time, rather than all ~144000 below `n`
+        let n = (BigUint::from(1u32) << 100_000u32) - 1u32;
+        let indices = zeckendorf(&n).unwrap();
+        assert!(indices.windows(2).all(|pair| pair[0] >= pair[1] + 2));
+        assert_eq!(from_zeckendorf(&indices), Ok(Some(n)));
+    }
+
+    #[test]
+    fn representations_are_valid() {
+        for n in 0u32..1000 {
+            let indices = zeckendorf(&BigUint::from(n)).unwrap();
+            assert!(
+                indices.windows(2).all(|pair| pair[0] >= pair[1] + 2),
+                "{indices:?}"
+            );
+            assert!(indices.iter().all(|&i| i >= 2));
+        }
+
+        assert_eq!(zeckendorf(&BigUint::from(100u32)), Ok(vec![11, 6, 4]));
+        assert_eq!(zeckendorf(&BigUint::ZERO), Ok(vec![]));
+    }
+
+    #[test]
+    fn invalid_representations() {
+        assert_eq!(from_zeckendorf(&[5, 4]), Ok(None));
+        assert_eq!(from_zeckendorf(&[1]), Ok(None));
+        assert_eq!(from_zeckendorf(&[6, 6]), Ok(None));
+    }
+
+    #[test]
+    fn indices_stay_below_the_limit() {
+        let limit = Err(FibError::ResourceLimit {
+            limit: (MAX_INDEX - 1).into(),
+        });
+        assert_eq!(from_zeckendorf(&[MAX_INDEX]), limit);
+        assert_eq!(from_zeckendorf(&[2, MAX_INDEX]), limit);
+    }
+}
@@ -0,0 +1,122 @@
+//! Differential tests of every fibonacci path against a slow but obviously correct reference,
+//! which adds up BigUints one by one
+
+use std::sync::LazyLock;
+
+use fib_core::{
+    FibError, FibonacciIter, FibonacciNumber, MAX_U128_N, fibonacci, fibonacci_big, fibonacci_mod,
+    fibonacci_number, fibonacci_pair_big, fibonacci_signed, pisano_period,
+};
+use num_bigint::{BigInt, BigUint};
+use proptest::prelude::*;
+
+/// How far the reference table goes, well past the u128 boundary
+const REFERENCE_LEN: u32 = 4000;
+
+/// F(0), ..., F(REFERENCE_LEN - 1), by naive addition
+static REFERENCE: LazyLock<Vec<BigUint>> = LazyLock::new(|| {
+    let mut table = vec![BigUint::ZERO, BigUint::from(1u8)];
+    while table.len() < REFERENCE_LEN as usize {
+        let next = &table[table.len() - 2] + &table[table.len() - 1];
+        table.push(next);
+    }
+    table
+});
+
+fn reference(n: u32) -> &'static BigUint {
+    &REFERENCE[n as usize]
+}
+
+/// F(n) for negative `n` as well, by F(-n) = (-1)^(n + 1) * F(n)
+fn reference_signed(n: i64) -> BigInt {
+    let magnitude = BigInt::from(reference(n.unsigned_abs() as u32).clone());
+    if n < 0 && n % 2 == 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+#[test]
+fn u128_path_ends_exactly_at_the_overflow_boundary() {
+    let max = BigUint::from(u128::MAX);
+    assert!(reference(MAX_U128_N) <= &max);
+    assert!(reference(MAX_U128_N + 1) > &max);
+
+    assert_eq!(
+        fibonacci(MAX_U128_N).map(BigUint::from).as_ref(),
+        Ok(reference(MAX_U128_N))
+    );
+    assert_eq!(
+        fibonacci(187),
+        Err(FibError::Overflow { n: 187, max_n: 186 })
+    );
+    assert_eq!(
+        fibonacci_number(187),
+        FibonacciNumber::Big(reference(187).clone())
+    );
+}
+
+proptest! {
+    #[test]
+    fn u128_path_matches_reference(n in 0..=MAX_U128_N + 64) {
+        let fits = reference(n) <= &BigUint::from(u128::MAX);
+        match fibonacci(n) {
+            Ok(result) => prop_assert_eq!(&BigUint::from(result), reference(n)),
+            Err(error) => {
+                prop_assert!(!fits);
+                prop_assert_eq!(error, FibError::Overflow { n: n.into(), max_n: MAX_U128_N.into() });
+            }
+        }
+        prop_assert_eq!(fibonacci(n).is_ok(), fits);
+    }
+
+    #[test]
+    fn big_path_matches_reference(n in 0..REFERENCE_LEN - 1) {
+        prop_assert_eq!(&fibonacci_big(n), reference(n));
+
+        let (a, b) = fibonacci_pair_big(n);
+        prop_assert_eq!(&a, reference(n));
+        prop_assert_eq!(&b, reference(n + 1));
+
+        // small results stay u128s, which the python conversion relies on
+        let number = fibonacci_number(n);
+        prop_assert_eq!(matches!(number, FibonacciNumber::Small(_)), n <= MAX_U128_N);
+        prop_assert_eq!(&BigUint::from(number), reference(n));
+    }
+
+    #[test]
+    fn signed_path_matches_reference(n in -(REFERENCE_LEN as i64 - 1)..REFERENCE_LEN as i64) {
+        let result = fibonacci_signed(n).unwrap();
+        let expected = reference_signed(n);
+        prop_assert_eq!(result.is_negative(), expected < BigInt::ZERO);
+        prop_assert_eq!(BigInt::from(result), expected);
+    }
+
+    #[test]
+    fn modular_path_matches_reference(n in -(REFERENCE_LEN as i64 - 1)..REFERENCE_LEN as i64, m in 1..=u128::MAX) {
+        let m_big = BigInt::from(m);
+        let expected = ((reference_signed(n) % &m_big) + &m_big) % &m_big;
+        prop_assert_eq!(BigInt::from(fibonacci_mod(&n.into(), m).unwrap()), expected);
+    }
+
+    #[test]
+    fn huge_modular_indices_repeat_with_the_pisano_period(
+        n in 0..REFERENCE_LEN,
+        cycles in 1u64..1 << 40,
+        m in 1..100_000u64,
+    ) {
+        let period = pisano_period(m).unwrap();
+        let huge = BigInt::from(n) + BigInt::from(period) * BigInt::from(cycles) * (BigInt::from(1) << 1200u32);
+        let expected = reference(n) % BigUint::from(m);
+        prop_assert_eq!(BigUint::from(fibonacci_mod(&huge, m.into()).unwrap()), expected);
+    }
+
+    #[test]
+    fn iterator_matches_reference(start in 0..REFERENCE_LEN, len in 0..300u32, step in 1..50u32) {
+        let stop = (start + len * step).min(REFERENCE_LEN);
+        let expected = (start..stop).step_by(step as usize).map(reference);
+        let actual = FibonacciIter::new(start, Some(stop), step).map(BigUint::from).collect::<Vec<_>>();
+        prop_assert!(actual.iter().eq(expected));
+    }
+}
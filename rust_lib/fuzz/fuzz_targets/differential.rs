@@ -0,0 +1,63 @@
+//! Compares every fibonacci path against naive BigUint addition
+//!
+//! Run it with `cargo +nightly fuzz run differential` from `rust_lib`.
+
+#![no_main]
+
+use fib_core::{
+    FibonacciNumber, MAX_U128_N, fibonacci, fibonacci_mod, fibonacci_number, fibonacci_signed,
+};
+use libfuzzer_sys::fuzz_target;
+use num_bigint::{BigInt, BigUint};
+
+/// Keeps the naive reference fast enough for fuzzing
+const MAX_N: u32 = 4096;
+
+/// F(n), by adding up BigUints one by one
+fn reference(n: u32) -> BigUint {
+    let (mut a, mut b) = (BigUint::ZERO, BigUint::from(1u8));
+    for _ in 0..n {
+        let next = &a + &b;
+        a = std::mem::replace(&mut b, next);
+    }
+    a
+}
+
+fuzz_target!(|input: (u16, bool, u128)| {
+    let (n, negative, m) = input;
+    let n = u32::from(n) % MAX_N;
+    let expected = reference(n);
+
+    // the u128 path has to fail exactly when the result does not fit
+    match fibonacci(n) {
+        Ok(result) => assert_eq!(BigUint::from(result), expected),
+        Err(_) => assert!(n > MAX_U128_N && expected > BigUint::from(u128::MAX)),
+    }
+
+    let number = fibonacci_number(n);
+    assert_eq!(matches!(number, FibonacciNumber::Small(_)), n <= MAX_U128_N);
+    assert_eq!(BigUint::from(number), expected);
+
+    let signed_n = if negative {
+        -i64::from(n)
+    } else {
+        i64::from(n)
+    };
+    let mut signed_expected = BigInt::from(expected);
+    if signed_n < 0 && signed_n % 2 == 0 {
+        signed_expected = -signed_expected;
+    }
+    assert_eq!(
+        BigInt::from(fibonacci_signed(signed_n).unwrap()),
+        signed_expected
+    );
+
+    if m > 0 {
+        let m_big = BigInt::from(m);
+        let reduced = ((&signed_expected % &m_big) + &m_big) % &m_big;
+        assert_eq!(
+            BigInt::from(fibonacci_mod(&signed_n.into(), m).unwrap()),
+            reduced
+        );
+    }
+});
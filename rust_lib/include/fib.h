@@ -0,0 +1,108 @@
+#ifndef FIB_H
+#define FIB_H
+
+/* Generated by cbindgen from src/capi.rs, do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The status code returned by every function of the C API
+ */
+typedef enum FibStatus {
+  /**
+   * The result has been written
+   */
+  FIB_STATUS_OK = 0,
+  /**
+   * The result does not fit into the output type
+   */
+  FIB_STATUS_OVERFLOW = 1,
+  /**
+   * The modulus is zero
+   */
+  FIB_STATUS_INVALID_MODULUS = 2,
+  /**
+   * The index is negative, but only non-negative indices are supported
+   */
+  FIB_STATUS_NEGATIVE_INDEX = 3,
+  /**
+   * The index is too large to compute the result
+   */
+  FIB_STATUS_RESOURCE_LIMIT = 4,
+  /**
+   * The output buffer is too small, the required capacity has been written to `len`
+   */
+  FIB_STATUS_BUFFER_TOO_SMALL = 5,
+  /**
+   * An output pointer is null
+   */
+  FIB_STATUS_NULL_POINTER = 6,
+} FibStatus;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Returns a static, null terminated description of the status code `status`
+ *
+ * This takes the code as an integer, so any value a C caller passes is safe.
+ */
+const char *fib_status_message(uint32_t status);
+
+/**
+ * Computes F(n) for all `n` up to 93, whose results fit into 64 bits
+ *
+ * # Safety
+ *
+ * `out` has to be null or valid for writes.
+ */
+enum FibStatus fib_u64(uint32_t n, uint64_t *out);
+
+/**
+ * Returns an upper bound on the number of limbs F(n) needs, for sizing the buffer of [`fib_big`]
+ */
+size_t fib_limbs_required(int64_t n);
+
+/**
+ * Computes F(n) with arbitrary precision, for positive and negative `n`
+ *
+ * The magnitude is written as `*len` little endian limbs into `limbs`, its sign into `negative`.
+ * If `capacity` is smaller than [`fib_limbs_required`], only that is written to `len` and
+ * [`FibStatus::BufferTooSmall`] is returned before computing anything,
+ * so `fib_big(n, NULL, 0, &len, &negative)` asks for the size of the buffer.
+ *
+ * # Safety
+ *
+ * `limbs` has to be valid for writing `capacity` u64s, it may only be null if `capacity` is 0.
+ * `len` and `negative` have to be null or valid for writes.
+ */
+enum FibStatus fib_big(int64_t n, uint64_t *limbs, size_t capacity, size_t *len, bool *negative);
+
+/**
+ * Computes F(n) mod m
+ *
+ * # Safety
+ *
+ * `out` has to be null or valid for writes.
+ */
+enum FibStatus fib_mod(uint64_t n, uint64_t m, uint64_t *out);
+
+/**
+ * Computes the Pisano period π(m), after which the fibonacci numbers mod m repeat
+ *
+ * # Safety
+ *
+ * `out` has to be null or valid for writes.
+ */
+enum FibStatus fib_pisano_period(uint64_t m, uint64_t *out);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* FIB_H */
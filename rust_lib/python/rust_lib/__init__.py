@@ -0,0 +1,4 @@
+"""Fibonacci numbers and related sequences, implemented in Rust"""
+
+from .rust_lib import *
+from .rust_lib import __all__
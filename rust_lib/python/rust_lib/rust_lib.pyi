@@ -0,0 +1,215 @@
+# Generated by `build.rs` from `src/lib.rs` and `src/exceptions.rs`, don't edit it by hand.
+# `tests/stubs.rs` checks that it is up to date and matches the compiled module,
+# `UPDATE_GENERATED=1 cargo test --test stubs` updates it.
+
+import os
+from collections.abc import Sequence
+from typing import Literal, overload
+
+import numpy as np
+import numpy.typing as npt
+from _typeshed import ReadableBuffer
+
+__all__: list[str]
+
+def implementation(n: int) -> int:
+    """Computes the `n`th fibonacci number as an arbitrarily large int,
+    negative indices follow F(-n) = (-1)^(n + 1) * F(n)"""
+
+def ffi_noop(n: int) -> int:
+    """Does nothing, but takes and returns the same types as `implementation`,
+    to measure the cost of calling into Rust"""
+
+def ffi_identity(n: int) -> int:
+    """Returns `n` through the same u128 conversion as the results of `implementation`"""
+
+def timed_implementation(n: int, iterations: int) -> int:
+    """Computes F(n) `iterations` times in a loop inside Rust and returns how many nanoseconds
+    that took in total, measured with a monotonic clock"""
+
+def fibonacci_u128(n: int) -> int:
+    """Computes the `n`th fibonacci number, raising a `FibonacciOverflowError`
+    instead of switching to arbitrary precision if it does not fit into 128 bits"""
+
+def fibonacci_table() -> memoryview:
+    """Returns a read-only memoryview of F(0) to F(186) as 16 byte little endian words,
+    e.g. for `numpy.frombuffer(fibonacci_table(), dtype="<u8").reshape(-1, 2)`"""
+
+def fibonacci_range(start: int, stop: int, threads: int | None = None) -> list[int]:
+    """Computes `[F(start), ..., F(stop - 1)]`, in parallel on `threads` threads"""
+
+def fibonacci_batch(indices: npt.ArrayLike) -> npt.NDArray[np.uint64] | npt.NDArray[np.object_]:
+    """Computes the fibonacci numbers for a whole array of indices at once
+
+    The result is a uint64 array if all values fit, a `(len, 2)` uint64 array
+    of little endian words if they fit into 128 bits and an object array of ints otherwise."""
+
+def is_fibonacci(x: int) -> bool:
+    """Checks whether `x` is a fibonacci number, negative values are checked against the negafibonacci numbers"""
+
+def fibonacci_index(x: int) -> int | None:
+    """Returns the index of `x` in the fibonacci sequence, or None if it is not a fibonacci number"""
+
+def is_fibonacci_batch(values: npt.ArrayLike) -> npt.NDArray[np.bool_]:
+    """Checks a whole array of values at once, returning a bool array"""
+
+def fibonacci_index_batch(values: npt.ArrayLike) -> npt.NDArray[np.int64]:
+    """Looks up the indices of a whole array of values at once
+
+    The result is an int64 array, which is -1 wherever the value is not a fibonacci number.
+    -1 is never a valid result, as `fibonacci_index(1)` is 1."""
+
+class FibonacciIterator:
+    """Lazily yields the fibonacci numbers `F(start), F(start + step), ...` up to, but excluding, `F(stop)`
+
+    An iterator can be shared between threads and never yields a value twice, but a call which
+    overlaps with a large computation on another thread raises a RuntimeError ("Already borrowed")
+    instead of waiting for it. Give every thread its own iterator, or guard a shared one with a lock."""
+
+    def __new__(cls, start: int = 0, stop: int | None = None, step: int = 1) -> FibonacciIterator: ...
+    def __iter__(self) -> FibonacciIterator: ...
+    def __next__(self) -> int: ...
+    def seek(self, n: int) -> None:
+        """Jumps to the `n`th fibonacci number without computing the ones in between"""
+
+    @property
+    def index(self) -> int | None:
+        """The index of the fibonacci number which will be returned next"""
+
+class LinearRecurrence:
+    """The linear recurrence a_n = c_1 * a_(n - 1) + ... + c_k * a_(n - k),
+    given by its coefficients `[c_1, ..., c_k]` and initial terms `[a_0, ..., a_(k - 1)]`"""
+
+    def __new__(cls, coefficients: Sequence[int], initial: Sequence[int], modulus: int | None = None) -> LinearRecurrence: ...
+    @property
+    def coefficients(self) -> list[int]:
+        """The coefficients `[c_1, ..., c_k]`"""
+
+    @property
+    def initial(self) -> list[int]:
+        """The initial terms `[a_0, ..., a_(k - 1)]`"""
+
+    @property
+    def modulus(self) -> int | None:
+        """The modulus all terms are reduced by, if there is one"""
+
+    @property
+    def order(self) -> int:
+        """The number of coefficients k"""
+
+    def nth(self, n: int) -> int:
+        """Computes the `n`th term without computing the ones before it"""
+
+    def nth_mod(self, n: int, m: int) -> int:
+        """Computes the `n`th term modulo `m`"""
+
+    def __iter__(self) -> LinearRecurrenceIterator: ...
+    def __repr__(self) -> str: ...
+
+class LinearRecurrenceIterator:
+    """Lazily yields the terms of a `LinearRecurrence`"""
+
+    def __iter__(self) -> LinearRecurrenceIterator: ...
+    def __next__(self) -> int: ...
+
+def berlekamp_massey(terms: Sequence[int], modulus: int | None = None) -> LinearRecurrence:
+    """Finds the shortest linear recurrence which generates `terms`,
+    over the integers or modulo a prime `modulus`"""
+
+@overload
+def zeckendorf(n: int, bitmask: Literal[False] = False) -> list[int]:
+    """Computes the Zeckendorf representation of `n`: the indices of the non-consecutive
+    fibonacci numbers summing up to `n` from largest to smallest, or, if `bitmask` is set,
+    an int with bit `i - 2` set for every F(i)"""
+
+@overload
+def zeckendorf(n: int, bitmask: Literal[True]) -> int: ...
+@overload
+def zeckendorf(n: int, bitmask: bool = False) -> list[int] | int: ...
+
+def from_zeckendorf(representation: int | Sequence[int]) -> int:
+    """Sums up a Zeckendorf representation, given as a list of indices or a bitmask"""
+
+def fibonacci_encode(values: list[int] | tuple[int, ...] | npt.NDArray[np.integer]) -> bytes:
+    """Fibonacci codes positive integers into a self-delimiting bitstream"""
+
+def fibonacci_decode(data: ReadableBuffer) -> npt.NDArray[np.uint64]:
+    """Decodes a complete fibonacci coded bitstream from any bytes-like object into a uint64 array"""
+
+class FibonacciEncoder:
+    """Incrementally fibonacci codes values, returning the bytes which are complete so far"""
+
+    def __new__(cls) -> FibonacciEncoder: ...
+    def write(self, values: list[int] | tuple[int, ...] | npt.NDArray[np.integer]) -> bytes:
+        """Encodes `values` and returns the bytes which are complete so far"""
+
+    def finish(self) -> bytes:
+        """Ends the stream and returns its padded last byte, the encoder can be reused afterwards"""
+
+class FibonacciDecoder:
+    """Incrementally decodes a fibonacci coded bitstream, codewords may be split across chunks"""
+
+    def __new__(cls) -> FibonacciDecoder: ...
+    def feed(self, data: ReadableBuffer) -> npt.NDArray[np.uint64]:
+        """Decodes the next chunk of the stream and returns the values which are complete so far"""
+
+    def finish(self) -> None:
+        """Ends the stream, raising a ValueError if it stopped in the middle of a codeword"""
+
+def fibonacci_mod(n: int, m: int) -> int:
+    """Computes F(n) mod m for an arbitrarily large, possibly negative `n`"""
+
+def lucas(n: int) -> int:
+    """Computes the `n`th lucas number L(n) = F(n - 1) + F(n + 1), for positive and negative `n`"""
+
+def lucas_mod(n: int, m: int) -> int:
+    """Computes L(n) mod m for an arbitrarily large, possibly negative `n`"""
+
+def lucas_u(n: int, p: int, q: int) -> int:
+    """Computes the lucas sequence U_n(P, Q), with U_n(1, -1) = F(n)"""
+
+def lucas_v(n: int, p: int, q: int) -> int:
+    """Computes the lucas sequence V_n(P, Q), with V_n(1, -1) = L(n)"""
+
+def lucas_u_mod(n: int, p: int, q: int, m: int) -> int:
+    """Computes U_n(P, Q) mod m for an arbitrarily large `n`"""
+
+def lucas_v_mod(n: int, p: int, q: int, m: int) -> int:
+    """Computes V_n(P, Q) mod m for an arbitrarily large `n`"""
+
+def pisano_period(m: int) -> int:
+    """Computes the Pisano period π(m), after which the fibonacci numbers mod m repeat
+
+    The periods are cached, as they are expensive to compute for large `m`."""
+
+def clear_pisano_cache() -> None:
+    """Empties the cache of `pisano_period`"""
+
+def set_cache_dir(path: str | os.PathLike[str] | None, max_size: int = ...) -> None:
+    """Caches the results of `implementation` and `fibonacci_mod` for large indices in the directory
+    `path`, so later runs don't have to compute them again, or stops caching if `path` is None
+
+    The least recently used files are deleted once the cache is larger than `max_size` bytes,
+    which defaults to 1 GiB. Multiple processes can share the same directory."""
+
+class FibonacciError(ArithmeticError):
+    """Base class for all errors raised while computing fibonacci numbers"""
+
+class FibonacciOverflowError(FibonacciError, OverflowError):
+    """The result does not fit into the requested integer type"""
+
+    n: int
+    max_n: int
+
+class InvalidModulusError(FibonacciError, ValueError):
+    """The modulus is zero, not prime where a prime is needed, or not the one of a recurrence"""
+
+class NegativeIndexError(FibonacciError, ValueError):
+    """The index is negative, but only non-negative indices are supported"""
+
+    n: int
+
+class ResourceLimitError(FibonacciError, ValueError):
+    """The index is too large to compute the result"""
+
+    limit: int
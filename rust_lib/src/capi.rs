@@ -0,0 +1,202 @@
+//! The C API, for calling the fibonacci functions from C, C++, Go and friends
+//!
+//! Every function returns a [`FibStatus`] and writes its results through pointers.
+//! Arbitrarily large results are written into caller allocated buffers of
+//! little endian u64 limbs, [`fib_limbs_required`] tells how large they have to be.
+//!
+//! The header `include/fib.h` is generated by cbindgen when building with the `capi` feature,
+//! `tests/capi.rs` checks that the committed one is up to date.
+
+use std::ffi::{CStr, c_char};
+
+use fib_core::{
+    FIB_U128, FibError, MAX_INDEX, MAX_U64_N, fibonacci_mod, fibonacci_signed, pisano_period,
+};
+use num_bigint::{BigInt, BigUint};
+
+/// log2(φ), the number of bits each fibonacci number adds
+const LOG2_PHI: f64 = 0.694_241_913_630_617_3;
+
+/// The status code returned by every function of the C API
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FibStatus {
+    /// The result has been written
+    Ok = 0,
+    /// The result does not fit into the output type
+    Overflow = 1,
+    /// The modulus is zero
+    InvalidModulus = 2,
+    /// The index is negative, but only non-negative indices are supported
+    NegativeIndex = 3,
+    /// The index is too large to compute the result
+    ResourceLimit = 4,
+    /// The output buffer is too small, the required capacity has been written to `len`
+    BufferTooSmall = 5,
+    /// An output pointer is null
+    NullPointer = 6,
+}
+
+impl From<FibError> for FibStatus {
+    fn from(error: FibError) -> FibStatus {
+        match error {
+            FibError::Overflow { .. } => FibStatus::Overflow,
+            FibError::InvalidModulus
+            | FibError::NonPrimeModulus { .. }
+            | FibError::ModulusMismatch { .. } => FibStatus::InvalidModulus,
+            FibError::NegativeIndex { .. } => FibStatus::NegativeIndex,
+            FibError::ResourceLimit { .. } => FibStatus::ResourceLimit,
+        }
+    }
+}
+
+/// Writes `value` to `out`, unless it is null
+///
+/// # Safety
+///
+/// `out` has to be null or valid for writes.
+unsafe fn write<T>(out: *mut T, value: T) -> FibStatus {
+    match unsafe { out.as_mut() } {
+        Some(out) => {
+            *out = value;
+            FibStatus::Ok
+        }
+        None => FibStatus::NullPointer,
+    }
+}
+
+impl FibStatus {
+    const ALL: [FibStatus; 7] = [
+        FibStatus::Ok,
+        FibStatus::Overflow,
+        FibStatus::InvalidModulus,
+        FibStatus::NegativeIndex,
+        FibStatus::ResourceLimit,
+        FibStatus::BufferTooSmall,
+        FibStatus::NullPointer,
+    ];
+
+    /// The status with the code `code`, if there is one
+    fn from_code(code: u32) -> Option<FibStatus> {
+        FibStatus::ALL
+            .into_iter()
+            .find(|&status| status as u32 == code)
+    }
+}
+
+/// Returns a static, null terminated description of the status code `status`
+///
+/// This takes the code as an integer, so any value a C caller passes is safe.
+#[unsafe(no_mangle)]
+pub extern "C" fn fib_status_message(status: u32) -> *const c_char {
+    let message: &'static CStr = match FibStatus::from_code(status) {
+        Some(FibStatus::Ok) => c"Success",
+        Some(FibStatus::Overflow) => c"The result does not fit into the output type",
+        Some(FibStatus::InvalidModulus) => c"The modulus must not be zero",
+        Some(FibStatus::NegativeIndex) => c"The index must not be negative",
+        Some(FibStatus::ResourceLimit) => c"The index is too large to compute the result",
+        Some(FibStatus::BufferTooSmall) => c"The output buffer is too small",
+        Some(FibStatus::NullPointer) => c"An output pointer is null",
+        None => c"Unknown status",
+    };
+    message.as_ptr()
+}
+
+/// Computes F(n) for all `n` up to 93, whose results fit into 64 bits
+///
+/// # Safety
+///
+/// `out` has to be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fib_u64(n: u32, out: *mut u64) -> FibStatus {
+    if n > MAX_U64_N {
+        return FibStatus::Overflow;
+    }
+
+    unsafe { write(out, FIB_U128[n as usize] as u64) }
+}
+
+/// Returns an upper bound on the number of limbs F(n) needs, for sizing the buffer of [`fib_big`]
+#[unsafe(no_mangle)]
+pub extern "C" fn fib_limbs_required(n: i64) -> usize {
+    // F(n) < φ^n, plus one limb to be safe from rounding
+    (n.unsigned_abs() as f64 * LOG2_PHI) as usize / 64 + 2
+}
+
+/// Computes F(n) with arbitrary precision, for positive and negative `n`
+///
+/// The magnitude is written as `*len` little endian limbs into `limbs`, its sign into `negative`.
+/// If `capacity` is smaller than [`fib_limbs_required`], only that is written to `len` and
+/// [`FibStatus::BufferTooSmall`] is returned before computing anything,
+/// so `fib_big(n, NULL, 0, &len, &negative)` asks for the size of the buffer.
+///
+/// # Safety
+///
+/// `limbs` has to be valid for writing `capacity` u64s, it may only be null if `capacity` is 0.
+/// `len` and `negative` have to be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fib_big(
+    n: i64,
+    limbs: *mut u64,
+    capacity: usize,
+    len: *mut usize,
+    negative: *mut bool,
+) -> FibStatus {
+    if (limbs.is_null() && capacity > 0) || len.is_null() || negative.is_null() {
+        return FibStatus::NullPointer;
+    }
+    if n.unsigned_abs() > MAX_INDEX.into() {
+        return FibStatus::ResourceLimit;
+    }
+
+    let required = fib_limbs_required(n);
+    if capacity < required {
+        unsafe { *len = required };
+        return FibStatus::BufferTooSmall;
+    }
+
+    let value = match fibonacci_signed(n) {
+        Ok(value) => value,
+        Err(error) => return error.into(),
+    };
+    let is_negative = value.is_negative();
+    let digits = BigUint::from(value.magnitude().clone()).to_u64_digits();
+
+    // `required` is an upper bound, so the digits always fit
+    unsafe {
+        std::ptr::copy_nonoverlapping(digits.as_ptr(), limbs, digits.len());
+        *len = digits.len();
+        *negative = is_negative;
+    }
+
+    FibStatus::Ok
+}
+
+/// Computes F(n) mod m
+///
+/// # Safety
+///
+/// `out` has to be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fib_mod(n: u64, m: u64, out: *mut u64) -> FibStatus {
+    match fibonacci_mod(&BigInt::from(n), m.into()) {
+        // the result is smaller than `m`
+        Ok(result) => unsafe { write(out, result as u64) },
+        Err(error) => error.into(),
+    }
+}
+
+/// Computes the Pisano period π(m), after which the fibonacci numbers mod m repeat
+///
+/// # Safety
+///
+/// `out` has to be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fib_pisano_period(m: u64, out: *mut u64) -> FibStatus {
+    match pisano_period(m).map(u64::try_from) {
+        Ok(Ok(period)) => unsafe { write(out, period) },
+        // π(m) <= 6m, which only overflows for huge moduli
+        Ok(Err(_)) => FibStatus::Overflow,
+        Err(error) => error.into(),
+    }
+}
@@ -0,0 +1,143 @@
+//! The python exception hierarchy [`FibError`]s are converted into
+//!
+//! ```text
+//! ArithmeticError
+//! └── FibonacciError
+//!     ├── FibonacciOverflowError (also an OverflowError)
+//!     ├── InvalidModulusError    (also a ValueError)
+//!     ├── NegativeIndexError     (also a ValueError)
+//!     └── ResourceLimitError     (also a ValueError)
+//! ```
+//!
+//! `pyo3::create_exception!` only supports a single base class, so the
+//! types are created by calling `type(name, bases, namespace)` instead.
+
+use pyo3::exceptions::{PyArithmeticError, PyOverflowError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::sync::PyOnceLock;
+use pyo3::types::{PyDict, PyTuple, PyType};
+
+use fib_core::FibError;
+use fib_core::coding::CodingError;
+
+/// The exception types, which have to be added to the python module to be catchable by name
+pub(crate) struct Exceptions {
+    fibonacci_error: Py<PyType>,
+    overflow_error: Py<PyType>,
+    invalid_modulus_error: Py<PyType>,
+    negative_index_error: Py<PyType>,
+    resource_limit_error: Py<PyType>,
+}
+
+static EXCEPTIONS: PyOnceLock<Exceptions> = PyOnceLock::new();
+
+impl Exceptions {
+    /// The exception types, which are created the first time they are needed
+    pub(crate) fn get(py: Python<'_>) -> PyResult<&'static Exceptions> {
+        EXCEPTIONS.get_or_try_init(py, || {
+            let fibonacci_error = new_exception(
+                py,
+                "FibonacciError",
+                "Base class for all errors raised while computing fibonacci numbers",
+                &[py.get_type::<PyArithmeticError>()],
+            )?;
+            let base = fibonacci_error.bind(py);
+
+            Ok(Exceptions {
+                overflow_error: new_exception(
+                    py,
+                    "FibonacciOverflowError",
+                    "The result does not fit into the requested integer type",
+                    &[base.clone(), py.get_type::<PyOverflowError>()],
+                )?,
+                invalid_modulus_error: new_exception(
+                    py,
+                    "InvalidModulusError",
+                    "The modulus is zero, not prime where a prime is needed, or not the one of a recurrence",
+                    &[base.clone(), py.get_type::<PyValueError>()],
+                )?,
+                negative_index_error: new_exception(
+                    py,
+                    "NegativeIndexError",
+                    "The index is negative, but only non-negative indices are supported",
+                    &[base.clone(), py.get_type::<PyValueError>()],
+                )?,
+                resource_limit_error: new_exception(
+                    py,
+                    "ResourceLimitError",
+                    "The index is too large to compute the result",
+                    &[base.clone(), py.get_type::<PyValueError>()],
+                )?,
+                fibonacci_error,
+            })
+        })
+    }
+
+    /// All exception types with the name they are exposed as
+    pub(crate) fn all(&self) -> [(&'static str, &Py<PyType>); 5] {
+        [
+            ("FibonacciError", &self.fibonacci_error),
+            ("FibonacciOverflowError", &self.overflow_error),
+            ("InvalidModulusError", &self.invalid_modulus_error),
+            ("NegativeIndexError", &self.negative_index_error),
+            ("ResourceLimitError", &self.resource_limit_error),
+        ]
+    }
+}
+
+fn new_exception(
+    py: Python<'_>,
+    name: &str,
+    doc: &str,
+    bases: &[Bound<'_, PyType>],
+) -> PyResult<Py<PyType>> {
+    let namespace = PyDict::new(py);
+    namespace.set_item("__module__", "rust_lib")?;
+    namespace.set_item("__doc__", doc)?;
+
+    let exception = py
+        .get_type::<PyType>()
+        .call1((name, PyTuple::new(py, bases)?, namespace))?;
+
+    Ok(exception.cast_into::<PyType>()?.unbind())
+}
+
+/// Converts a [`FibError`] into its python exception, `From` can't be implemented for foreign types
+pub(crate) fn to_pyerr(error: FibError) -> PyErr {
+    Python::attach(|py| {
+        let build = || {
+            let exceptions = Exceptions::get(py)?;
+            let exception_type = match error {
+                FibError::Overflow { .. } => &exceptions.overflow_error,
+                FibError::InvalidModulus
+                | FibError::NonPrimeModulus { .. }
+                | FibError::ModulusMismatch { .. } => &exceptions.invalid_modulus_error,
+                FibError::NegativeIndex { .. } => &exceptions.negative_index_error,
+                FibError::ResourceLimit { .. } => &exceptions.resource_limit_error,
+            };
+
+            // expose the fields as attributes, so callers don't have to parse the message
+            let exception = exception_type.bind(py).call1((error.to_string(),))?;
+            match error {
+                FibError::Overflow { n, max_n } => {
+                    exception.setattr("n", n)?;
+                    exception.setattr("max_n", max_n)?;
+                }
+                FibError::NegativeIndex { n } => exception.setattr("n", n)?,
+                FibError::ResourceLimit { limit } => exception.setattr("limit", limit)?,
+                FibError::InvalidModulus
+                | FibError::NonPrimeModulus { .. }
+                | FibError::ModulusMismatch { .. } => {}
+            }
+
+            PyResult::Ok(PyErr::from_value(exception))
+        };
+
+        build().unwrap_or_else(|err| err)
+    })
+}
+
+/// Malformed input and streams are plain `ValueError`s, they are unrelated to computing fibonacci numbers
+pub(crate) fn coding_error(error: CodingError) -> PyErr {
+    PyValueError::new_err(error.to_string())
+}
@@ -1,34 +1,711 @@
-/// A pure Rust function to compute the `n`th fibonacci number or None
-/// if it does not fit into a u128
-///
-/// Python will no be able to see this function unless you expose it in a `pyo3::pymodule`
-fn fibonacci(n: u32) -> Option<u128> {
-    let mut a: u128 = 0;
-    let mut b: u128 = 1;
+//! The python and C bindings for `fib-core`, which holds the actual implementation
 
-    for _ in 0..n {
-        (a, b) = (b, a.checked_add(b)?);
-    }
-
-    Some(a)
-}
+#[cfg(feature = "capi")]
+mod capi;
+#[cfg(feature = "python")]
+mod exceptions;
+#[cfg(feature = "python")]
+mod python;
 
 /// The module which will be exposed to python
 /// all functions declared as `#[pyfunction]`s in here will
 /// be visible from python
-#[pyo3::pymodule]
-mod rust_lib {
-    use super::*;
+#[cfg(feature = "python")]
+#[pyo3::pymodule(gil_used = false)]
+pub mod rust_lib {
+    use fib_core::coding::{self, Decoder, Encoder};
+    use fib_core::disk_cache;
+    use fib_core::zeckendorf::{from_bitmask, to_bitmask};
+    use fib_core::*;
+    use num_bigint::{BigInt, BigUint};
 
-    use pyo3::exceptions::PyOverflowError;
+    use numpy::ndarray::Array2;
+    use numpy::{
+        AllowTypeChange, IntoPyArray, PyArray1, PyArrayDescrMethods, PyArrayLike1, PyUntypedArray,
+        PyUntypedArrayMethods,
+    };
+    use pyo3::buffer::PyBuffer;
+    use pyo3::exceptions::{PyTypeError, PyValueError};
     use pyo3::prelude::*;
+    use pyo3::types::{PyBytes, PyInt, PyList, PyTuple};
+    use std::hint::black_box;
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use std::time::Instant;
+
+    use crate::exceptions::{Exceptions, coding_error, to_pyerr};
+    use crate::python::PythonInt;
+
+    /// Converts the index of an arbitrarily large python int
+    fn signed_index(n: &Bound<'_, PyInt>) -> Result<i64, FibError> {
+        // anything which doesn't fit into an i64 is way past `MAX_INDEX` anyway
+        n.extract().map_err(|_| FibError::ResourceLimit {
+            limit: MAX_INDEX.into(),
+        })
+    }
+
+    /// Computes the `n`th fibonacci number as an arbitrarily large int,
+    /// negative indices follow F(-n) = (-1)^(n + 1) * F(n)
+    #[pyfunction]
+    fn implementation(
+        py: Python<'_>,
+        n: &Bound<'_, PyInt>,
+    ) -> PyResult<PythonInt<SignedFibonacciNumber>> {
+        let n = signed_index(n).map_err(to_pyerr)?;
+
+        // every result which fits into a u128 is a lookup in `FIB_U128`,
+        // so releasing the GIL is only worth it once the computation gets expensive
+        let value = if n.unsigned_abs() <= MAX_U128_N.into() {
+            fibonacci_signed(n)
+        } else {
+            py.detach(|| fibonacci_signed_cached(n))
+        };
+        value.map(PythonInt).map_err(to_pyerr)
+    }
+
+    /// Does nothing, but takes and returns the same types as `implementation`,
+    /// to measure the cost of calling into Rust
+    #[pyfunction]
+    fn ffi_noop(n: u32) -> u128 {
+        let _ = n;
+        0
+    }
+
+    /// Returns `n` through the same u128 conversion as the results of `implementation`
+    #[pyfunction]
+    fn ffi_identity(n: u32) -> u128 {
+        n.into()
+    }
+
+    /// Computes F(n) `iterations` times in a loop inside Rust and returns how many nanoseconds
+    /// that took in total, measured with a monotonic clock
+    #[pyfunction]
+    fn timed_implementation(py: Python<'_>, n: i64, iterations: u64) -> PyResult<u128> {
+        // raise errors up front, instead of timing them
+        fibonacci_signed(n).map_err(to_pyerr)?;
+
+        Ok(py.detach(|| {
+            let start = Instant::now();
+            for _ in 0..iterations {
+                black_box(fibonacci_signed(black_box(n)).ok());
+            }
+            start.elapsed().as_nanos()
+        }))
+    }
+
+    /// Computes the `n`th fibonacci number, raising a `FibonacciOverflowError`
+    /// instead of switching to arbitrary precision if it does not fit into 128 bits
+    #[pyfunction]
+    fn fibonacci_u128(n: u32) -> PyResult<u128> {
+        fibonacci(n).map_err(to_pyerr)
+    }
+
+    /// Returns a read-only memoryview of F(0) to F(186) as 16 byte little endian words,
+    /// e.g. for `numpy.frombuffer(fibonacci_table(), dtype="<u8").reshape(-1, 2)`
+    #[pyfunction]
+    fn fibonacci_table(py: Python<'_>) -> PyResult<Bound<'_, PyAny>> {
+        // SAFETY: the bytes are a `static` and the memoryview is read only, so it can never
+        // outlive or modify them
+        unsafe {
+            let view = pyo3::ffi::PyMemoryView_FromMemory(
+                FIB_U128_LE_BYTES.as_ptr().cast_mut().cast(),
+                FIB_U128_LE_BYTES.len() as pyo3::ffi::Py_ssize_t,
+                pyo3::ffi::PyBUF_READ,
+            );
+            Bound::from_owned_ptr_or_err(py, view)
+        }
+    }
+
+    /// Computes `[F(start), ..., F(stop - 1)]`, in parallel on `threads` threads
+    #[pyfunction]
+    #[pyo3(name = "fibonacci_range", signature = (start, stop, threads = None))]
+    fn py_fibonacci_range(
+        py: Python<'_>,
+        start: i64,
+        stop: i64,
+        threads: Option<usize>,
+    ) -> PyResult<Bound<'_, PyList>> {
+        let start = unsigned_index(start).map_err(to_pyerr)?;
+        let stop = unsigned_index(stop).map_err(to_pyerr)?;
+        let threads = threads
+            .map(|threads| {
+                NonZeroUsize::new(threads)
+                    .ok_or_else(|| PyValueError::new_err("The number of threads must not be zero"))
+            })
+            .transpose()?;
+
+        let values = py.detach(|| fibonacci_range(start, stop, threads));
+        PyList::new(py, values.into_iter().map(PythonInt))
+    }
+
+    /// Computes the fibonacci numbers for a whole array of indices at once
+    ///
+    /// The result is a uint64 array if all values fit, a `(len, 2)` uint64 array
+    /// of little endian words if they fit into 128 bits and an object array of ints otherwise.
+    #[pyfunction]
+    fn fibonacci_batch<'py>(
+        py: Python<'py>,
+        indices: PyArrayLike1<'py, i64, AllowTypeChange>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let indices = indices.as_array().to_vec();
+
+        let min_n = indices.iter().copied().min().unwrap_or(0);
+        let max_n = indices.iter().copied().max().unwrap_or(0);
+
+        // negative indices can have negative results, which need an object array
+        if min_n >= 0 && max_n <= MAX_U64_N.into() {
+            let values = py.detach(|| {
+                indices
+                    .iter()
+                    .map(|&n| fibonacci(n as u32).unwrap() as u64)
+                    .collect::<Vec<_>>()
+            });
+            Ok(values.into_pyarray(py).into_any())
+        } else if min_n >= 0 && max_n <= MAX_U128_N.into() {
+            let words = py.detach(|| {
+                let values = indices
+                    .iter()
+                    .map(|&n| fibonacci(n as u32).unwrap())
+                    .collect::<Vec<_>>();
+                Array2::from_shape_fn((values.len(), 2), |(i, word)| {
+                    (values[i] >> (64 * word)) as u64
+                })
+            });
+            Ok(words.into_pyarray(py).into_any())
+        } else {
+            let values = py
+                .detach(|| {
+                    indices
+                        .iter()
+                        .map(|&n| fibonacci_signed(n))
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .map_err(to_pyerr)?;
+            let objects = values
+                .into_iter()
+                .map(|value| Ok(PythonInt(value).into_pyobject(py)?.into_any().unbind()))
+                .collect::<PyResult<Vec<_>>>()?;
+            Ok(objects.into_pyarray(py).into_any())
+        }
+    }
+
+    /// Checks whether `x` is a fibonacci number, negative values are checked against the negafibonacci numbers
+    #[pyfunction]
+    #[pyo3(name = "is_fibonacci")]
+    fn py_is_fibonacci(py: Python<'_>, x: BigInt) -> bool {
+        py.detach(|| is_fibonacci(&x))
+    }
+
+    /// Returns the index of `x` in the fibonacci sequence, or None if it is not a fibonacci number
+    #[pyfunction]
+    #[pyo3(name = "fibonacci_index")]
+    fn py_fibonacci_index(py: Python<'_>, x: BigInt) -> PyResult<Option<i64>> {
+        py.detach(|| fibonacci_index(&x)).map_err(to_pyerr)
+    }
+
+    /// Checks a whole array of values at once, returning a bool array
+    #[pyfunction]
+    fn is_fibonacci_batch<'py>(
+        py: Python<'py>,
+        values: PyArrayLike1<'py, i64, AllowTypeChange>,
+    ) -> Bound<'py, PyArray1<bool>> {
+        let values = values.as_array().to_vec();
+        let results = py.detach(|| {
+            values
+                .iter()
+                .map(|&x| is_fibonacci(&x.into()))
+                .collect::<Vec<_>>()
+        });
+        results.into_pyarray(py)
+    }
+
+    /// Looks up the indices of a whole array of values at once
+    ///
+    /// The result is an int64 array, which is -1 wherever the value is not a fibonacci number.
+    /// -1 is never a valid result, as `fibonacci_index(1)` is 1.
+    #[pyfunction]
+    fn fibonacci_index_batch<'py>(
+        py: Python<'py>,
+        values: PyArrayLike1<'py, i64, AllowTypeChange>,
+    ) -> PyResult<Bound<'py, PyArray1<i64>>> {
+        let values = values.as_array().to_vec();
+        let indices = py
+            .detach(|| {
+                values
+                    .iter()
+                    .map(|&x| Ok(fibonacci_index(&x.into())?.unwrap_or(-1)))
+                    .collect::<Result<Vec<_>, FibError>>()
+            })
+            .map_err(to_pyerr)?;
+        Ok(indices.into_pyarray(py))
+    }
+
+    /// Lazily yields the fibonacci numbers `F(start), F(start + step), ...` up to, but excluding, `F(stop)`
+    ///
+    /// An iterator can be shared between threads and never yields a value twice, but a call which
+    /// overlaps with a large computation on another thread raises a RuntimeError ("Already borrowed")
+    /// instead of waiting for it. Give every thread its own iterator, or guard a shared one with a lock.
+    #[pyclass(name = "FibonacciIterator")]
+    struct PyFibonacciIterator {
+        inner: FibonacciIter,
+    }
+
+    #[pymethods]
+    impl PyFibonacciIterator {
+        #[new]
+        #[pyo3(signature = (start = 0, stop = None, step = 1))]
+        fn new(start: i64, stop: Option<i64>, step: i64) -> PyResult<Self> {
+            if step == 0 {
+                return Err(PyValueError::new_err("The step must not be zero"));
+            }
+
+            let start = unsigned_index(start).map_err(to_pyerr)?;
+            let stop = stop.map(unsigned_index).transpose().map_err(to_pyerr)?;
+            let step = unsigned_index(step).map_err(to_pyerr)?;
+            Ok(Self {
+                inner: FibonacciIter::new(start, stop, step),
+            })
+        }
+
+        fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+            slf
+        }
+
+        fn __next__(&mut self, py: Python<'_>) -> Option<PythonInt<FibonacciNumber>> {
+            let value = if self.inner.is_small() {
+                self.inner.next()
+            } else {
+                py.detach(|| self.inner.next())
+            };
+            value.map(PythonInt)
+        }
+
+        /// Jumps to the `n`th fibonacci number without computing the ones in between
+        fn seek(&mut self, py: Python<'_>, n: i64) -> PyResult<()> {
+            let n = unsigned_index(n).map_err(to_pyerr)?;
+            py.detach(|| self.inner.seek(n));
+            Ok(())
+        }
+
+        /// The index of the fibonacci number which will be returned next
+        #[getter]
+        fn index(&self) -> Option<u32> {
+            self.inner.index()
+        }
+    }
+
+    /// The linear recurrence a_n = c_1 * a_(n - 1) + ... + c_k * a_(n - k),
+    /// given by its coefficients `[c_1, ..., c_k]` and initial terms `[a_0, ..., a_(k - 1)]`
+    #[pyclass(name = "LinearRecurrence", frozen)]
+    struct PyLinearRecurrence {
+        inner: LinearRecurrence,
+    }
+
+    #[pymethods]
+    impl PyLinearRecurrence {
+        #[new]
+        #[pyo3(signature = (coefficients, initial, modulus = None))]
+        fn new(
+            coefficients: Vec<BigInt>,
+            initial: Vec<BigInt>,
+            modulus: Option<u128>,
+        ) -> PyResult<Self> {
+            if coefficients.is_empty() {
+                return Err(PyValueError::new_err(
+                    "A recurrence needs at least one coefficient",
+                ));
+            }
+            if coefficients.len() != initial.len() {
+                return Err(PyValueError::new_err(
+                    "A recurrence needs as many initial terms as coefficients",
+                ));
+            }
+
+            let mut inner = LinearRecurrence::new(coefficients, initial);
+            if let Some(modulus) = modulus {
+                inner = inner.with_modulus(modulus).map_err(to_pyerr)?;
+            }
+
+            Ok(Self { inner })
+        }
+
+        /// The coefficients `[c_1, ..., c_k]`
+        #[getter]
+        fn coefficients(&self) -> Vec<BigInt> {
+            self.inner.coefficients().to_vec()
+        }
+
+        /// The initial terms `[a_0, ..., a_(k - 1)]`
+        #[getter]
+        fn initial(&self) -> Vec<BigInt> {
+            self.inner.initial().to_vec()
+        }
+
+        /// The modulus all terms are reduced by, if there is one
+        #[getter]
+        fn modulus(&self) -> Option<u128> {
+            self.inner.modulus()
+        }
+
+        /// The number of coefficients k
+        #[getter]
+        fn order(&self) -> usize {
+            self.inner.order()
+        }
+
+        /// Computes the `n`th term without computing the ones before it
+        fn nth(&self, py: Python<'_>, n: i64) -> PyResult<BigInt> {
+            let n = unsigned_index(n).map_err(to_pyerr)?;
+            Ok(py.detach(|| self.inner.nth(n)))
+        }
+
+        /// Computes the `n`th term modulo `m`
+        fn nth_mod(&self, py: Python<'_>, n: BigInt, m: u128) -> PyResult<u128> {
+            let n = unsigned_big_index(n).map_err(to_pyerr)?;
+            py.detach(|| self.inner.nth_mod(&n, m)).map_err(to_pyerr)
+        }
+
+        fn __iter__(&self) -> PyLinearRecurrenceIterator {
+            PyLinearRecurrenceIterator {
+                inner: self.inner.iter(),
+            }
+        }
+
+        fn __repr__(&self) -> String {
+            let list = |values: &[BigInt]| {
+                let values = values.iter().map(ToString::to_string).collect::<Vec<_>>();
+                format!("[{}]", values.join(", "))
+            };
+
+            let coefficients = list(self.inner.coefficients());
+            let initial = list(self.inner.initial());
+            match self.inner.modulus() {
+                Some(modulus) => {
+                    format!("LinearRecurrence({coefficients}, {initial}, modulus={modulus})")
+                }
+                None => format!("LinearRecurrence({coefficients}, {initial})"),
+            }
+        }
+    }
 
+    /// Lazily yields the terms of a `LinearRecurrence`
+    #[pyclass(name = "LinearRecurrenceIterator")]
+    struct PyLinearRecurrenceIterator {
+        inner: LinearRecurrenceIter,
+    }
+
+    #[pymethods]
+    impl PyLinearRecurrenceIterator {
+        fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+            slf
+        }
+
+        fn __next__(&mut self) -> Option<BigInt> {
+            self.inner.next()
+        }
+    }
+
+    /// Finds the shortest linear recurrence which generates `terms`,
+    /// over the integers or modulo a prime `modulus`
+    #[pyfunction]
+    #[pyo3(name = "berlekamp_massey", signature = (terms, modulus = None))]
+    fn py_berlekamp_massey(
+        py: Python<'_>,
+        terms: Vec<BigInt>,
+        modulus: Option<u64>,
+    ) -> PyResult<PyLinearRecurrence> {
+        let inner = match modulus {
+            Some(modulus) => py
+                .detach(|| berlekamp_massey_mod(&terms, modulus))
+                .map_err(to_pyerr)?,
+            None => py.detach(|| berlekamp_massey(&terms)).ok_or_else(|| {
+                PyValueError::new_err(
+                    "The terms are not generated by a recurrence with integer coefficients",
+                )
+            })?,
+        };
+
+        Ok(PyLinearRecurrence { inner })
+    }
+
+    /// Computes the Zeckendorf representation of `n`: the indices of the non-consecutive
+    /// fibonacci numbers summing up to `n` from largest to smallest, or, if `bitmask` is set,
+    /// an int with bit `i - 2` set for every F(i)
     #[pyfunction]
-    fn implementation(n: u32) -> PyResult<u128> {
-        fibonacci(n).ok_or_else(|| {
-            PyOverflowError::new_err(format!(
-                "Overflow occured while computing the {n}th fibonacci number"
+    #[pyo3(name = "zeckendorf", signature = (n, bitmask = false))]
+    fn py_zeckendorf(py: Python<'_>, n: BigInt, bitmask: bool) -> PyResult<Bound<'_, PyAny>> {
+        let n = n.to_biguint().ok_or_else(|| {
+            PyValueError::new_err("Only non-negative integers have a zeckendorf representation")
+        })?;
+
+        let indices = py.detach(|| zeckendorf(&n)).map_err(to_pyerr)?;
+        if bitmask {
+            Ok(to_bitmask(&indices).into_pyobject(py)?.into_any())
+        } else {
+            Ok(PyList::new(py, indices)?.into_any())
+        }
+    }
+
+    /// Sums up a Zeckendorf representation, given as a list of indices or a bitmask
+    #[pyfunction]
+    #[pyo3(name = "from_zeckendorf")]
+    fn py_from_zeckendorf(py: Python<'_>, representation: &Bound<'_, PyAny>) -> PyResult<BigUint> {
+        let indices = match representation.cast::<PyInt>() {
+            Ok(bitmask) => from_bitmask(&bitmask.extract()?).map_err(to_pyerr)?,
+            Err(_) => representation.extract()?,
+        };
+
+        let value = py.detach(|| from_zeckendorf(&indices)).map_err(to_pyerr)?;
+        value.ok_or_else(|| {
+            PyValueError::new_err(
+                "A zeckendorf representation consists of distinct, non-consecutive indices of at least 2",
+            )
+        })
+    }
+
+    /// Extracts the values to fibonacci code from a list or tuple of ints or a NumPy integer array
+    fn coding_values(values: &Bound<'_, PyAny>) -> PyResult<Vec<u64>> {
+        // the encoder rejects zeros, but negative values never get that far
+        let negative = |position: usize, value: &dyn std::fmt::Display| {
+            PyValueError::new_err(format!(
+                "Only positive integers can be fibonacci coded, got {value} at position {position}"
             ))
+        };
+
+        if values.is_instance_of::<PyList>() || values.is_instance_of::<PyTuple>() {
+            return values
+                .try_iter()?
+                .enumerate()
+                .map(|(position, value)| {
+                    let value = value?;
+                    match value.extract::<u64>() {
+                        Ok(value) => Ok(value),
+                        Err(_) if value.is_instance_of::<PyInt>() && value.lt(0)? => {
+                            Err(negative(position, &value))
+                        }
+                        Err(error) => Err(error),
+                    }
+                })
+                .collect();
+        }
+
+        let array = values.cast::<PyUntypedArray>()?;
+        match array.dtype().kind() {
+            b'u' => Ok(values
+                .extract::<PyArrayLike1<'_, u64, AllowTypeChange>>()?
+                .as_array()
+                .to_vec()),
+            b'i' => values
+                .extract::<PyArrayLike1<'_, i64, AllowTypeChange>>()?
+                .as_array()
+                .iter()
+                .enumerate()
+                .map(|(position, &value)| {
+                    u64::try_from(value).map_err(|_| negative(position, &value))
+                })
+                .collect(),
+            _ => Err(PyTypeError::new_err(
+                "Only integer arrays can be fibonacci coded",
+            )),
+        }
+    }
+
+    /// Fibonacci codes positive integers into a self-delimiting bitstream
+    #[pyfunction]
+    fn fibonacci_encode<'py>(
+        py: Python<'py>,
+        values: &Bound<'py, PyAny>,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let values = coding_values(values)?;
+        let bytes = py
+            .detach(|| coding::encode(&values))
+            .map_err(coding_error)?;
+        Ok(PyBytes::new(py, &bytes))
+    }
+
+    /// Decodes a complete fibonacci coded bitstream from any bytes-like object into a uint64 array
+    #[pyfunction]
+    fn fibonacci_decode<'py>(
+        py: Python<'py>,
+        data: PyBuffer<u8>,
+    ) -> PyResult<Bound<'py, PyArray1<u64>>> {
+        let bytes = data.to_vec(py)?;
+        let values = py.detach(|| coding::decode(&bytes)).map_err(coding_error)?;
+        Ok(values.into_pyarray(py))
+    }
+
+    /// Incrementally fibonacci codes values, returning the bytes which are complete so far
+    #[pyclass(name = "FibonacciEncoder")]
+    #[derive(Default)]
+    struct PyFibonacciEncoder {
+        inner: Encoder,
+    }
+
+    #[pymethods]
+    impl PyFibonacciEncoder {
+        #[new]
+        fn new() -> Self {
+            Self::default()
+        }
+
+        /// Encodes `values` and returns the bytes which are complete so far
+        fn write<'py>(
+            &mut self,
+            py: Python<'py>,
+            values: &Bound<'py, PyAny>,
+        ) -> PyResult<Bound<'py, PyBytes>> {
+            let values = coding_values(values)?;
+            let encoder = &mut self.inner;
+            let bytes = py
+                .detach(|| {
+                    values
+                        .iter()
+                        .try_for_each(|&value| encoder.write(value))
+                        .map(|()| encoder.take_bytes())
+                })
+                .map_err(coding_error)?;
+            Ok(PyBytes::new(py, &bytes))
+        }
+
+        /// Ends the stream and returns its padded last byte, the encoder can be reused afterwards
+        fn finish<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyBytes> {
+            PyBytes::new(py, &std::mem::take(&mut self.inner).finish())
+        }
+    }
+
+    /// Incrementally decodes a fibonacci coded bitstream, codewords may be split across chunks
+    #[pyclass(name = "FibonacciDecoder")]
+    #[derive(Default)]
+    struct PyFibonacciDecoder {
+        inner: Decoder,
+    }
+
+    #[pymethods]
+    impl PyFibonacciDecoder {
+        #[new]
+        fn new() -> Self {
+            Self::default()
+        }
+
+        /// Decodes the next chunk of the stream and returns the values which are complete so far
+        fn feed<'py>(
+            &mut self,
+            py: Python<'py>,
+            data: PyBuffer<u8>,
+        ) -> PyResult<Bound<'py, PyArray1<u64>>> {
+            let bytes = data.to_vec(py)?;
+            let decoder = &mut self.inner;
+            let values = py
+                .detach(|| {
+                    let mut values = Vec::new();
+                    decoder.feed(&bytes, &mut values).map(|()| values)
+                })
+                .map_err(coding_error)?;
+            Ok(values.into_pyarray(py))
+        }
+
+        /// Ends the stream, raising a ValueError if it stopped in the middle of a codeword
+        fn finish(&mut self) -> PyResult<()> {
+            std::mem::take(&mut self.inner)
+                .finish()
+                .map_err(coding_error)
+        }
+    }
+
+    /// Computes F(n) mod m for an arbitrarily large, possibly negative `n`
+    #[pyfunction]
+    #[pyo3(name = "fibonacci_mod")]
+    fn py_fibonacci_mod(py: Python<'_>, n: BigInt, m: u128) -> PyResult<u128> {
+        py.detach(|| fibonacci_mod_cached(&n, m)).map_err(to_pyerr)
+    }
+
+    /// Converts an arbitrarily large index for the modular functions which don't support negative indices
+    fn unsigned_big_index(n: BigInt) -> Result<BigUint, FibError> {
+        n.to_biguint().ok_or_else(|| FibError::NegativeIndex {
+            n: i64::try_from(&n).unwrap_or(i64::MIN),
         })
     }
+
+    /// Computes the `n`th lucas number L(n) = F(n - 1) + F(n + 1), for positive and negative `n`
+    #[pyfunction]
+    #[pyo3(name = "lucas")]
+    fn py_lucas(py: Python<'_>, n: &Bound<'_, PyInt>) -> PyResult<BigInt> {
+        let n = signed_index(n).map_err(to_pyerr)?;
+        py.detach(|| lucas(n)).map_err(to_pyerr)
+    }
+
+    /// Computes L(n) mod m for an arbitrarily large, possibly negative `n`
+    #[pyfunction]
+    #[pyo3(name = "lucas_mod")]
+    fn py_lucas_mod(py: Python<'_>, n: BigInt, m: u128) -> PyResult<u128> {
+        py.detach(|| lucas_mod(&n, m)).map_err(to_pyerr)
+    }
+
+    /// Computes the lucas sequence U_n(P, Q), with U_n(1, -1) = F(n)
+    #[pyfunction]
+    #[pyo3(name = "lucas_u")]
+    fn py_lucas_u(py: Python<'_>, n: i64, p: BigInt, q: BigInt) -> PyResult<BigInt> {
+        let n = unsigned_index(n).map_err(to_pyerr)?;
+        Ok(py.detach(|| lucas_u(n, &p, &q)))
+    }
+
+    /// Computes the lucas sequence V_n(P, Q), with V_n(1, -1) = L(n)
+    #[pyfunction]
+    #[pyo3(name = "lucas_v")]
+    fn py_lucas_v(py: Python<'_>, n: i64, p: BigInt, q: BigInt) -> PyResult<BigInt> {
+        let n = unsigned_index(n).map_err(to_pyerr)?;
+        Ok(py.detach(|| lucas_v(n, &p, &q)))
+    }
+
+    /// Computes U_n(P, Q) mod m for an arbitrarily large `n`
+    #[pyfunction]
+    #[pyo3(name = "lucas_u_mod")]
+    fn py_lucas_u_mod(py: Python<'_>, n: BigInt, p: BigInt, q: BigInt, m: u128) -> PyResult<u128> {
+        let n = unsigned_big_index(n).map_err(to_pyerr)?;
+        py.detach(|| lucas_u_mod(&n, &p, &q, m)).map_err(to_pyerr)
+    }
+
+    /// Computes V_n(P, Q) mod m for an arbitrarily large `n`
+    #[pyfunction]
+    #[pyo3(name = "lucas_v_mod")]
+    fn py_lucas_v_mod(py: Python<'_>, n: BigInt, p: BigInt, q: BigInt, m: u128) -> PyResult<u128> {
+        let n = unsigned_big_index(n).map_err(to_pyerr)?;
+        py.detach(|| lucas_v_mod(&n, &p, &q, m)).map_err(to_pyerr)
+    }
+
+    /// Computes the Pisano period π(m), after which the fibonacci numbers mod m repeat
+    ///
+    /// The periods are cached, as they are expensive to compute for large `m`.
+    #[pyfunction]
+    #[pyo3(name = "pisano_period")]
+    fn py_pisano_period(py: Python<'_>, m: u64) -> PyResult<u128> {
+        py.detach(|| pisano_period(m)).map_err(to_pyerr)
+    }
+
+    /// Empties the cache of `pisano_period`
+    #[pyfunction]
+    #[pyo3(name = "clear_pisano_cache")]
+    fn py_clear_pisano_cache() {
+        clear_pisano_cache();
+    }
+
+    /// Caches the results of `implementation` and `fibonacci_mod` for large indices in the directory
+    /// `path`, so later runs don't have to compute them again, or stops caching if `path` is None
+    ///
+    /// The least recently used files are deleted once the cache is larger than `max_size` bytes,
+    /// which defaults to 1 GiB. Multiple processes can share the same directory.
+    #[pyfunction]
+    #[pyo3(name = "set_cache_dir", signature = (path, max_size = disk_cache::DEFAULT_CACHE_SIZE))]
+    fn py_set_cache_dir(path: Option<PathBuf>, max_size: u64) -> PyResult<()> {
+        Ok(set_cache_dir(path, max_size)?)
+    }
+
+    #[pymodule_init]
+    fn init(m: &Bound<'_, PyModule>) -> PyResult<()> {
+        for (name, exception) in Exceptions::get(m.py())?.all() {
+            m.add(name, exception)?;
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,43 @@
+//! Conversions of the core types into python objects
+
+use fib_core::{FibonacciNumber, SignedFibonacciNumber};
+use num_bigint::BigInt;
+use pyo3::prelude::*;
+use pyo3::types::PyInt;
+
+/// Converts the fibonacci numbers of `fib-core` into python ints,
+/// which can't implement `IntoPyObject` there without depending on pyo3
+pub(crate) struct PythonInt<T>(pub T);
+
+impl<'py> IntoPyObject<'py> for PythonInt<FibonacciNumber> {
+    type Target = PyInt;
+    type Output = Bound<'py, PyInt>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> PyResult<Bound<'py, PyInt>> {
+        // `BigUint`s are converted through their little endian bytes,
+        // so large results never have to go through a decimal string
+        match self.0 {
+            FibonacciNumber::Small(value) => Ok(value.into_pyobject(py)?),
+            FibonacciNumber::Big(value) => value.into_pyobject(py),
+        }
+    }
+}
+
+impl<'py> IntoPyObject<'py> for PythonInt<SignedFibonacciNumber> {
+    type Target = PyInt;
+    type Output = Bound<'py, PyInt>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> PyResult<Bound<'py, PyInt>> {
+        let value = self.0;
+        match (value.is_negative(), value.magnitude()) {
+            (false, FibonacciNumber::Small(magnitude)) => Ok(magnitude.into_pyobject(py)?),
+            (false, FibonacciNumber::Big(magnitude)) => magnitude.into_pyobject(py),
+            (true, &FibonacciNumber::Small(magnitude)) if magnitude <= i128::MAX as u128 => {
+                Ok((-(magnitude as i128)).into_pyobject(py)?)
+            }
+            _ => BigInt::from(value).into_pyobject(py),
+        }
+    }
+}
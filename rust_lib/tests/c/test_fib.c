@@ -0,0 +1,78 @@
+/* Checks the C API against known fibonacci numbers, run it with `make` */
+
+#include <assert.h>
+#include <inttypes.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "fib.h"
+
+static void test_u64(void) {
+    uint64_t result;
+    assert(fib_u64(0, &result) == FIB_STATUS_OK && result == 0);
+    assert(fib_u64(10, &result) == FIB_STATUS_OK && result == 55);
+    assert(fib_u64(93, &result) == FIB_STATUS_OK && result == UINT64_C(12200160415121876738));
+    assert(fib_u64(94, &result) == FIB_STATUS_OVERFLOW);
+    assert(fib_u64(10, NULL) == FIB_STATUS_NULL_POINTER);
+}
+
+static void test_big(void) {
+    uint64_t limbs[4];
+    size_t len;
+    bool negative;
+
+    /* F(100) = 354224848179261915075 = 19 * 2^64 + 3736710778780434371 */
+    assert(fib_limbs_required(100) <= 4);
+    assert(fib_big(100, limbs, 4, &len, &negative) == FIB_STATUS_OK);
+    assert(len == 2 && !negative);
+    assert(limbs[0] == UINT64_C(3736710778780434371) && limbs[1] == 19);
+
+    /* F(-100) = -F(100) */
+    assert(fib_big(-100, limbs, 4, &len, &negative) == FIB_STATUS_OK);
+    assert(len == 2 && negative);
+
+    /* F(0) has no limbs at all */
+    assert(fib_big(0, limbs, 4, &len, &negative) == FIB_STATUS_OK && len == 0);
+
+    /* F(1000) needs 11 limbs, the required capacity is a bound with some slack */
+    assert(fib_big(1000, limbs, 4, &len, &negative) == FIB_STATUS_BUFFER_TOO_SMALL);
+    assert(len == fib_limbs_required(1000) && len >= 11);
+
+    /* without a buffer, only the required capacity is written */
+    assert(fib_big(1000, NULL, 0, &len, &negative) == FIB_STATUS_BUFFER_TOO_SMALL);
+    assert(len == fib_limbs_required(1000));
+    uint64_t *large = malloc(len * sizeof(uint64_t));
+    assert(large != NULL);
+    assert(fib_big(1000, large, len, &len, &negative) == FIB_STATUS_OK && len == 11);
+    assert(large[10] == UINT64_C(9527040750744258) && !negative);
+    free(large);
+
+    assert(fib_big(1000, NULL, 4, &len, &negative) == FIB_STATUS_NULL_POINTER);
+
+    assert(fib_big(INT64_MAX, limbs, 4, &len, &negative) == FIB_STATUS_RESOURCE_LIMIT);
+}
+
+static void test_modular(void) {
+    uint64_t result;
+    assert(fib_mod(UINT64_MAX, 1000000007, &result) == FIB_STATUS_OK);
+    assert(fib_mod(100, 1000, &result) == FIB_STATUS_OK && result == 75);
+    assert(fib_mod(100, 0, &result) == FIB_STATUS_INVALID_MODULUS);
+
+    assert(fib_pisano_period(10, &result) == FIB_STATUS_OK && result == 60);
+    assert(fib_pisano_period(0, &result) == FIB_STATUS_INVALID_MODULUS);
+
+    assert(strcmp(fib_status_message(FIB_STATUS_INVALID_MODULUS), "The modulus must not be zero") == 0);
+    /* codes which are not a FibStatus are safe as well */
+    assert(strcmp(fib_status_message(42), "Unknown status") == 0);
+    assert(strcmp(fib_status_message(UINT32_MAX), "Unknown status") == 0);
+}
+
+int main(void) {
+    test_u64();
+    test_big();
+    test_modular();
+
+    printf("All C API tests passed\n");
+    return 0;
+}
@@ -0,0 +1,25 @@
+//! Checks that the committed C header is the one `build.rs` generates
+//!
+//! Run it with `cargo test --features capi --test capi`, `make -C tests/c` does as well.
+
+#![cfg(feature = "capi")]
+
+/// The header the C programs include
+const HEADER_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/include/fib.h");
+
+#[test]
+fn header_is_up_to_date() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/fib.h"));
+    let committed = std::fs::read_to_string(HEADER_PATH).unwrap();
+    if generated == committed {
+        return;
+    }
+
+    if std::env::var_os("UPDATE_GENERATED").is_some() {
+        std::fs::write(HEADER_PATH, generated).unwrap();
+    } else {
+        panic!(
+            "{HEADER_PATH} is out of date, run `UPDATE_GENERATED=1 cargo test --features capi --test capi`"
+        );
+    }
+}
@@ -0,0 +1,475 @@
+//! Integration tests of the python module in an embedded interpreter
+//!
+//! Run them with `cargo test --features python-tests`.
+
+#![cfg(feature = "python-tests")]
+
+use std::sync::{LazyLock, Once};
+
+use num_bigint::{BigInt, BigUint};
+use proptest::prelude::*;
+use pyo3::exceptions::{PyOverflowError, PyTypeError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyInt};
+use rust_lib::rust_lib as module;
+
+/// Runs `test` with the module imported as `rust_lib`
+fn with_module<T>(test: impl FnOnce(Python<'_>, &Bound<'_, PyModule>) -> PyResult<T>) -> T {
+    // the module has to be registered before the interpreter starts
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| pyo3::append_to_inittab!(module));
+
+    Python::attach(|py| {
+        let module = py.import("rust_lib")?;
+        test(py, &module)
+    })
+    .unwrap()
+}
+
+/// Runs `test` like [`with_module`], but skips it if NumPy is not installed
+fn with_numpy(test: impl FnOnce(Python<'_>, &Bound<'_, PyModule>) -> PyResult<()>) {
+    with_module(|py, module| {
+        if py.import("numpy").is_err() {
+            eprintln!("skipped, NumPy is not installed");
+            return Ok(());
+        }
+        test(py, module)
+    });
+}
+
+/// Evaluates a python expression with the module in scope
+fn eval<'py>(
+    py: Python<'py>,
+    module: &Bound<'py, PyModule>,
+    code: &str,
+) -> PyResult<Bound<'py, PyAny>> {
+    let globals = PyDict::new(py);
+    globals.set_item("rust_lib", module)?;
+    py.eval(&std::ffi::CString::new(code)?, Some(&globals), None)
+}
+
+#[test]
+fn implementation_matches_python() {
+    with_module(|py, module| {
+        let globals = PyDict::new(py);
+        globals.set_item("rust_lib", module)?;
+        // the same loop as python_lib, extended to negative indices
+        py.run(
+            cr#"
+def fibonacci(n):
+    a, b = 0, 1
+    for _ in range(abs(n)):
+        a, b = b, a + b
+    return -a if n < 0 and n % 2 == 0 else a
+
+for n in range(-400, 400):
+    result = rust_lib.implementation(n)
+    assert type(result) is int, (n, type(result))
+    assert result == fibonacci(n), (n, result)
+"#,
+            Some(&globals),
+            None,
+        )
+    });
+}
+
+#[test]
+fn overflow_raises_the_fibonacci_exception() {
+    with_module(|py, module| {
+        let error = eval(py, module, "rust_lib.fibonacci_u128(187)").unwrap_err();
+
+        let overflow_error = module.getattr("FibonacciOverflowError")?;
+        assert!(error.get_type(py).is(&overflow_error));
+        assert!(error.is_instance_of::<PyOverflowError>(py));
+        assert!(error.matches(py, module.getattr("FibonacciError")?)?);
+        assert_eq!(
+            error.value(py).to_string(),
+            "Overflow occurred while computing the 187th fibonacci number (the largest index which fits is 186)"
+        );
+
+        let value = error.value(py);
+        assert_eq!(value.getattr("n")?.extract::<i64>()?, 187);
+        assert_eq!(value.getattr("max_n")?.extract::<i64>()?, 186);
+
+        assert_eq!(
+            eval(py, module, "rust_lib.fibonacci_u128(186)")?.extract::<u128>()?,
+            332_825_110_087_067_562_321_196_029_789_634_457_848
+        );
+        Ok(())
+    });
+}
+
+#[test]
+fn huge_indices_raise_a_resource_limit_error() {
+    with_module(|py, module| {
+        let error = eval(py, module, "rust_lib.implementation(2**32)").unwrap_err();
+        assert!(error.matches(py, module.getattr("ResourceLimitError")?)?);
+        assert_eq!(
+            error.value(py).to_string(),
+            "Indices larger than 4294967295 are not supported"
+        );
+        Ok(())
+    });
+}
+
+#[test]
+fn invalid_arguments_are_rejected() {
+    with_module(|py, module| {
+        // `implementation` supports negative indices, but the u32 functions don't
+        assert_eq!(
+            eval(py, module, "rust_lib.implementation(-6)")?.extract::<i64>()?,
+            -8
+        );
+
+        let error = eval(py, module, "rust_lib.fibonacci_u128(-1)").unwrap_err();
+        assert!(error.is_instance_of::<PyOverflowError>(py));
+        assert_eq!(
+            error.value(py).to_string(),
+            "out of range integral type conversion attempted"
+        );
+
+        for code in [
+            "rust_lib.FibonacciIterator(-1)",
+            "rust_lib.FibonacciIterator(0, -1)",
+            "rust_lib.FibonacciIterator(0, 10, -1)",
+            "rust_lib.fibonacci_range(-1, 10)",
+            "rust_lib.fibonacci_range(0, -1)",
+        ] {
+            let error = eval(py, module, code).unwrap_err();
+            assert!(
+                error.matches(py, module.getattr("NegativeIndexError")?)?,
+                "{code}: {error}"
+            );
+        }
+
+        // `zeckendorf` never returns the index 2**32 - 1, so it is rejected rather than wrapped around
+        let error = eval(py, module, "rust_lib.from_zeckendorf([2**32 - 1])").unwrap_err();
+        assert!(error.matches(py, module.getattr("ResourceLimitError")?)?);
+
+        let error = eval(py, module, "rust_lib.berlekamp_massey([1, 1, 2], 91)").unwrap_err();
+        assert!(error.matches(py, module.getattr("InvalidModulusError")?)?);
+        assert_eq!(
+            error.value(py).to_string(),
+            "The modulus has to be prime, got 91"
+        );
+
+        let code = "rust_lib.LinearRecurrence([1, 1], [0, 1], modulus=1000).nth_mod(5, 7)";
+        let error = eval(py, module, code).unwrap_err();
+        assert!(error.matches(py, module.getattr("InvalidModulusError")?)?);
+
+        for code in [
+            "rust_lib.implementation('10')",
+            "rust_lib.implementation(10.0)",
+            "rust_lib.implementation(None)",
+            "rust_lib.fibonacci_u128('10')",
+        ] {
+            let error = eval(py, module, code).unwrap_err();
+            assert!(error.is_instance_of::<PyTypeError>(py), "{code}: {error}");
+            assert!(
+                error.value(py).to_string().contains("argument 'n'"),
+                "{code}: {error}"
+            );
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn coding_rejects_negative_values() {
+    with_module(|py, module| {
+        let globals = PyDict::new(py);
+        globals.set_item("rust_lib", module)?;
+        py.run(
+            cr#"
+try:
+    import numpy
+except ImportError:
+    numpy = None
+
+inputs = [[1, 2, -3], (1, 2, -3)]
+if numpy is not None:
+    inputs.append(numpy.array([1, 2, -3], dtype=numpy.int64))
+
+for values in inputs:
+    for encode in [rust_lib.fibonacci_encode, rust_lib.FibonacciEncoder().write]:
+        try:
+            encode(values)
+        except ValueError as error:
+            assert str(error) == "Only positive integers can be fibonacci coded, got -3 at position 2", error
+        else:
+            raise AssertionError(f"{values!r} was encoded")
+
+# zeros are still rejected by the encoder itself
+try:
+    rust_lib.fibonacci_encode([1, 0])
+except ValueError as error:
+    assert "got a zero at position 1" in str(error), error
+else:
+    raise AssertionError("0 was encoded")
+"#,
+            Some(&globals),
+            None,
+        )
+    });
+}
+
+#[test]
+fn batches_return_numpy_arrays() {
+    with_numpy(|py, module| {
+        let globals = PyDict::new(py);
+        globals.set_item("rust_lib", module)?;
+        py.run(
+            cr#"
+import numpy
+
+def check(array, dtype, values):
+    assert array.dtype == dtype, (array.dtype, dtype)
+    assert array.tolist() == values, (array.tolist(), values)
+
+# small results are plain uint64s
+check(rust_lib.fibonacci_batch([0, 1, 93]), numpy.uint64, [0, 1, 12200160415121876738])
+check(rust_lib.fibonacci_batch(numpy.array([10, 20], dtype=numpy.int32)), numpy.uint64, [55, 6765])
+
+# up to F(186) they are split into little endian 64 bit words
+words = rust_lib.fibonacci_batch([94, 186])
+assert words.dtype == numpy.uint64 and words.shape == (2, 2), (words.dtype, words.shape)
+for n, (low, high) in zip([94, 186], words.tolist()):
+    assert low + (high << 64) == rust_lib.implementation(n), n
+
+# negative and larger indices fall back to python ints
+for indices in [[-2, 5], [-186, 3], [187], [5, 1000]]:
+    objects = rust_lib.fibonacci_batch(indices)
+    check(objects, object, [rust_lib.implementation(n) for n in indices])
+    assert all(type(value) is int for value in objects), indices
+
+check(rust_lib.fibonacci_batch([]), numpy.uint64, [])
+assert rust_lib.fibonacci_batch([]).shape == (0,)
+
+check(
+    rust_lib.is_fibonacci_batch(numpy.array([0, 1, 4, 144, -8, 7540113804746346429])),
+    numpy.bool_,
+    [True, True, False, True, True, True],
+)
+check(rust_lib.is_fibonacci_batch([]), numpy.bool_, [])
+
+check(rust_lib.fibonacci_index_batch([0, 4, 144, -8]), numpy.int64, [0, -1, 12, -6])
+check(rust_lib.fibonacci_index_batch([]), numpy.int64, [])
+"#,
+            Some(&globals),
+            None,
+        )
+    });
+}
+
+#[test]
+fn decoding_returns_numpy_arrays() {
+    with_numpy(|py, module| {
+        let globals = PyDict::new(py);
+        globals.set_item("rust_lib", module)?;
+        py.run(
+            cr#"
+import numpy
+
+values = [1, 2, 3, 10**18, 2**64 - 1]
+data = rust_lib.fibonacci_encode(numpy.array(values, dtype=numpy.uint64))
+
+for source in [data, bytearray(data), numpy.frombuffer(data, dtype=numpy.uint8)]:
+    decoded = rust_lib.fibonacci_decode(source)
+    assert decoded.dtype == numpy.uint64, decoded.dtype
+    assert decoded.tolist() == values, decoded
+
+empty = rust_lib.fibonacci_decode(b"")
+assert empty.dtype == numpy.uint64 and empty.shape == (0,), empty
+
+# codewords split across chunks only come out once they are complete
+decoder = rust_lib.FibonacciDecoder()
+chunks = [decoder.feed(data[i:i + 1]) for i in range(len(data))] + [decoder.feed(b"")]
+assert all(chunk.dtype == numpy.uint64 for chunk in chunks)
+assert numpy.concatenate(chunks).tolist() == values
+assert decoder.feed(b"").shape == (0,)
+decoder.finish()
+"#,
+            Some(&globals),
+            None,
+        )
+    });
+}
+
+#[test]
+fn threads_get_the_serial_results() {
+    with_module(|py, module| {
+        let globals = PyDict::new(py);
+        globals.set_item("rust_lib", module)?;
+        // the functions release the GIL while computing, so they run concurrently from a thread pool
+        py.run(
+            cr#"
+import threading
+from concurrent.futures import ThreadPoolExecutor
+
+indices = list(range(-300, 300)) + [10_000, 100_000, 200_000]
+moduli = [2, 10, 2**61 - 1, 2**127 - 1]
+expected = [rust_lib.implementation(n) for n in indices]
+expected_mod = [[rust_lib.fibonacci_mod(n, m) for m in moduli] for n in indices]
+expected_range = rust_lib.fibonacci_range(0, 2000)
+
+with ThreadPoolExecutor(max_workers=4) as pool:
+    assert list(pool.map(rust_lib.implementation, indices)) == expected
+    assert list(pool.map(lambda n: [rust_lib.fibonacci_mod(n, m) for m in moduli], indices)) == expected_mod
+    ranges = pool.map(lambda start: rust_lib.fibonacci_range(start, start + 100, threads=2), range(0, 2000, 100))
+    assert [f for chunk in ranges for f in chunk] == expected_range
+
+# a shared iterator hands out every value exactly once, calls which overlap with
+# a computation on another thread raise a RuntimeError instead of waiting for it
+iterator = rust_lib.FibonacciIterator(100_000, 100_200)
+values, lock = [], threading.Lock()
+
+def consume():
+    while True:
+        try:
+            value = next(iterator)
+        except StopIteration:
+            return
+        except RuntimeError as error:
+            assert "borrowed" in str(error), error
+            continue
+        with lock:
+            values.append(value)
+
+with ThreadPoolExecutor(max_workers=4) as pool:
+    for future in [pool.submit(consume) for _ in range(4)]:
+        future.result()
+assert sorted(values) == list(rust_lib.FibonacciIterator(100_000, 100_200))
+
+# the getter can't read the index while `seek` computes on another thread
+iterator = rust_lib.FibonacciIterator()
+started, errors = threading.Event(), []
+
+def seek():
+    started.set()
+    iterator.seek(3_000_000)
+    next(iterator)
+
+with ThreadPoolExecutor(max_workers=1) as pool:
+    future = pool.submit(seek)
+    started.wait()
+    while not future.done():
+        try:
+            iterator.index
+        except RuntimeError as error:
+            errors.append(str(error))
+    future.result()
+assert errors and all(error == "Already mutably borrowed" for error in errors), errors
+assert iterator.index == 3_000_001
+"#,
+            Some(&globals),
+            None,
+        )
+    });
+}
+
+#[test]
+fn cache_dir_stores_large_results() {
+    with_module(|py, module| {
+        let globals = PyDict::new(py);
+        globals.set_item("rust_lib", module)?;
+        py.run(
+            cr#"
+import os, pathlib, tempfile
+
+with tempfile.TemporaryDirectory() as path:
+    rust_lib.set_cache_dir(pathlib.Path(path))
+    try:
+        n, m = 300_000, 2**127 - 1
+        expected = rust_lib.implementation(n), rust_lib.fibonacci_mod(3**1000, m)
+        assert len(os.listdir(path)) == 2, os.listdir(path)
+
+        assert (rust_lib.implementation(n), rust_lib.fibonacci_mod(3**1000, m)) == expected
+        assert rust_lib.implementation(-n) == -expected[0]
+        assert len(os.listdir(path)) == 2, os.listdir(path)
+    finally:
+        rust_lib.set_cache_dir(None)
+"#,
+            Some(&globals),
+            None,
+        )
+    });
+}
+
+/// How far the reference table goes, well past the u128 boundary
+const REFERENCE_LEN: usize = 2000;
+
+/// F(0), ..., F(REFERENCE_LEN - 1), by naive addition
+static REFERENCE: LazyLock<Vec<BigUint>> = LazyLock::new(|| {
+    let mut table = vec![BigUint::ZERO, BigUint::from(1u8)];
+    while table.len() < REFERENCE_LEN {
+        let next = &table[table.len() - 2] + &table[table.len() - 1];
+        table.push(next);
+    }
+    table
+});
+
+/// F(n) for negative `n` as well, by F(-n) = (-1)^(n + 1) * F(n)
+fn reference_signed(n: i64) -> BigInt {
+    let magnitude = BigInt::from(REFERENCE[n.unsigned_abs() as usize].clone());
+    if n < 0 && n % 2 == 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// Indices up to the length of the reference, with the boundaries where the conversion into python
+/// ints changes: F(186) is the last u128 and F(-186) the first negative result beyond an i128
+fn signed_indices() -> impl Strategy<Value = i64> {
+    let max = REFERENCE_LEN as i64 - 1;
+    prop_oneof![-190i64..=-180, 180i64..=190, -max..=max]
+}
+
+proptest! {
+    #[test]
+    fn implementation_matches_reference(n in signed_indices()) {
+        let (exact_int, result) = with_module(|_, module| {
+            let result = module.getattr("implementation")?.call1((n,))?;
+            Ok((result.is_exact_instance_of::<PyInt>(), result.extract::<BigInt>()?))
+        });
+        prop_assert!(exact_int);
+        prop_assert_eq!(result, reference_signed(n));
+    }
+
+    #[test]
+    fn fibonacci_u128_matches_reference(n in prop_oneof![Just(186u32), Just(187), 0..=250u32]) {
+        let result = with_module(|py, module| {
+            match module.getattr("fibonacci_u128")?.call1((n,)) {
+                Ok(result) => Ok(Ok(result.extract::<BigUint>()?)),
+                Err(error) if error.matches(py, module.getattr("FibonacciOverflowError")?)? => {
+                    let error = error.value(py);
+                    Ok(Err((error.getattr("n")?.extract::<u32>()?, error.getattr("max_n")?.extract::<u32>()?)))
+                }
+                Err(error) => Err(error),
+            }
+        });
+
+        match result {
+            Ok(result) => {
+                prop_assert!(n <= 186);
+                prop_assert_eq!(&result, &REFERENCE[n as usize]);
+            }
+            Err(overflow) => {
+                prop_assert!(REFERENCE[n as usize] > BigUint::from(u128::MAX));
+                prop_assert_eq!(overflow, (n, 186));
+            }
+        }
+    }
+
+    #[test]
+    fn fibonacci_mod_matches_reference(
+        n in signed_indices(),
+        m in prop_oneof![1..=1000u128, 1..=u64::MAX as u128, 1..=u128::MAX],
+    ) {
+        let result = with_module(|_, module| {
+            module.getattr("fibonacci_mod")?.call1((n, m))?.extract::<u128>()
+        });
+        let m = BigInt::from(m);
+        prop_assert_eq!(BigInt::from(result), (reference_signed(n) % &m + &m) % &m);
+    }
+}
@@ -0,0 +1,271 @@
+//! Checks that the committed type stubs are the ones `build.rs` generates,
+//! and checks them against the compiled python module in an embedded interpreter
+//!
+//! Unlike `tests/python.rs` this runs in the default `cargo test`,
+//! but it also needs a python installation with its shared library.
+
+#![cfg(feature = "python")]
+
+use std::ffi::CStr;
+use std::sync::Once;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rust_lib::rust_lib as module;
+
+/// The stubs shipped in the python package
+const STUBS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/python/rust_lib/rust_lib.pyi");
+
+#[test]
+fn stubs_are_up_to_date() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/rust_lib.pyi"));
+    let committed = std::fs::read_to_string(STUBS_PATH).unwrap();
+    if generated == committed {
+        return;
+    }
+
+    if std::env::var_os("UPDATE_GENERATED").is_some() {
+        std::fs::write(STUBS_PATH, generated).unwrap();
+    } else {
+        panic!("{STUBS_PATH} is out of date, run `UPDATE_GENERATED=1 cargo test --test stubs`");
+    }
+}
+
+/// Parses the stubs into `functions` and `classes` and defines the helpers the scripts of the tests use
+const PARSE_STUBS: &CStr = cr#"
+import ast, builtins, collections.abc, copy, functools, inspect
+
+try:
+    import numpy
+except ImportError:
+    numpy = None
+
+def conforms(value, annotation):
+    """Whether `value` has the type of a stubbed annotation, as far as that can be checked at runtime"""
+    match annotation:
+        case ast.BinOp(op=ast.BitOr(), left=left, right=right):
+            return conforms(value, left) or conforms(value, right)
+        case ast.Constant(value=None):
+            return value is None
+        case ast.Name(id="ReadableBuffer"):
+            try:
+                memoryview(value)
+                return True
+            except TypeError:
+                return False
+        case ast.Name(id=name) if hasattr(builtins, name):
+            # a bool is an int, but not the other way around
+            return isinstance(value, getattr(builtins, name)) and (name == "bool" or type(value) is not bool)
+        case ast.Name(id=name):
+            return isinstance(value, getattr(rust_lib, name))
+        case ast.Subscript(value=ast.Name(id="Literal"), slice=literal):
+            literals = literal.elts if isinstance(literal, ast.Tuple) else [literal]
+            return any(type(value) is type(literal.value) and value == literal.value for literal in literals)
+        case ast.Subscript(value=ast.Name(id="tuple"), slice=ast.Tuple(elts=[element, ast.Constant(value=Ellipsis)])):
+            return isinstance(value, tuple) and all(conforms(item, element) for item in value)
+        case ast.Subscript(value=ast.Name(id="list" | "Sequence" as container), slice=element):
+            expected = list if container == "list" else collections.abc.Sequence
+            return isinstance(value, expected) and not isinstance(value, str) and all(conforms(item, element) for item in value)
+        case ast.Attribute(value=ast.Name(id="npt"), attr="ArrayLike"):
+            return True
+        case ast.Subscript(value=ast.Attribute(value=ast.Name(id="npt"), attr="NDArray"), slice=ast.Attribute(value=ast.Name(id="np"), attr=scalar)):
+            return numpy is not None and isinstance(value, numpy.ndarray) and issubclass(value.dtype.type, getattr(numpy, scalar))
+    raise AssertionError(f"can't check the annotation {ast.unparse(annotation)}")
+
+def signature(function):
+    """The signature of a stubbed function, without annotations"""
+    function = copy.deepcopy(function)
+    arguments = function.args
+    for argument in arguments.posonlyargs + arguments.args + arguments.kwonlyargs:
+        argument.annotation = None
+    function.returns = None
+    function.decorator_list = []
+    function.body = [ast.Pass()]
+    namespace = {}
+    exec(compile(ast.fix_missing_locations(ast.Module([function], [])), "<stubs>", "exec"), namespace)
+    return inspect.signature(namespace[function.name])
+
+def check_annotations(function, known):
+    arguments = function.args
+    annotated = arguments.posonlyargs + arguments.args + arguments.kwonlyargs
+    for argument in annotated[1:] if annotated and annotated[0].arg in ("self", "cls") else annotated:
+        assert argument.annotation is not None, (function.name, argument.arg)
+    assert function.returns is not None, function.name
+    for annotation in [argument.annotation for argument in annotated] + [function.returns]:
+        for node in ast.walk(annotation or ast.Constant(None)):
+            if isinstance(node, ast.Name):
+                assert node.id in known, (function.name, node.id)
+
+def parameters(signature, skip_first):
+    return list(signature.parameters.values())[1 if skip_first else 0:]
+
+def check_functions(stubbed, owner, name, is_method):
+    # the last overload is the most general one, but the docstring is on the first
+    last = stubbed[-1]
+    for function in stubbed:
+        check_annotations(function, known)
+    docstring = next(filter(None, map(ast.get_docstring, stubbed)), None)
+    is_dunder = name.startswith("__")
+    assert is_dunder or docstring, f"{name} has no docstring"
+
+    runtime = getattr(owner, name)
+    if docstring:
+        assert inspect.getdoc(runtime) == docstring, (name, inspect.getdoc(runtime), docstring)
+
+    if any(isinstance(d, ast.Name) and d.id == "property" for d in last.decorator_list):
+        assert inspect.isdatadescriptor(runtime), name
+        return
+
+    expected = parameters(inspect.signature(owner if name == "__new__" else runtime), is_method and name != "__new__")
+    actual = parameters(signature(last), is_method)
+    assert expected == actual, (name, expected, actual)
+
+def definitions(body):
+    functions, classes = {}, {}
+    for node in body:
+        if isinstance(node, ast.FunctionDef):
+            functions.setdefault(node.name, []).append(node)
+        elif isinstance(node, ast.ClassDef):
+            classes[node.name] = node
+    return functions, classes
+
+tree = ast.parse(stubs)
+functions, classes = definitions(tree.body)
+imported = {alias.asname or alias.name for node in tree.body if isinstance(node, (ast.Import, ast.ImportFrom)) for alias in node.names}
+known = imported | set(classes) | {name for name, value in vars(builtins).items() if isinstance(value, type)}
+assert set(functions) | set(classes) == set(rust_lib.__all__), set(functions) ^ set(classes) ^ set(rust_lib.__all__)
+"#;
+
+/// The tables of hand-written annotations in the stub generator
+const STUB_GENERATOR: &str = include_str!("../build/stubs.rs");
+
+/// Runs `script` with the module imported as `rust_lib` and the stubs parsed by [`PARSE_STUBS`]
+fn check_stubs(script: &CStr) {
+    // the module has to be registered before the interpreter starts
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        pyo3::append_to_inittab!(module);
+        Python::initialize();
+    });
+
+    Python::attach(|py| {
+        let globals = PyDict::new(py);
+        globals.set_item("rust_lib", py.import("rust_lib")?)?;
+        globals.set_item("stubs", std::fs::read_to_string(STUBS_PATH)?)?;
+        globals.set_item("stub_generator", STUB_GENERATOR)?;
+        py.run(PARSE_STUBS, Some(&globals), None)?;
+        py.run(script, Some(&globals), None)
+    })
+    .unwrap();
+}
+
+#[test]
+fn stubs_match_the_module() {
+    // compares the parameters and docstrings of everything in the stubs against
+    // the `__text_signature__`s and `__doc__`s of the compiled module,
+    // and checks that every annotation only uses names the stubs define or import
+    check_stubs(cr#"
+for name, stubbed in functions.items():
+    assert getattr(rust_lib, name).__text_signature__ is not None, name
+    check_functions(stubbed, rust_lib, name, is_method=False)
+
+for name, stubbed in classes.items():
+    cls = getattr(rust_lib, name)
+    assert inspect.getdoc(cls) == ast.get_docstring(stubbed), name
+    assert [base.__name__ for base in cls.__bases__] == [base.id for base in stubbed.bases] or (not stubbed.bases and cls.__bases__ == (object,)), name
+
+    methods, _ = definitions(stubbed.body)
+    if not issubclass(cls, BaseException):
+        stubbed_members = set(methods) - {"__new__"}
+        members = set(vars(cls)) - {"__doc__", "__module__", "__new__"}
+        assert stubbed_members == members, (name, stubbed_members ^ members)
+        assert ("__new__" in methods) == (cls.__text_signature__ is not None), name
+    for method, stubbed_method in methods.items():
+        check_functions(stubbed_method, cls, method, is_method=True)
+"#);
+}
+
+#[test]
+fn exceptions_have_the_stubbed_attributes() {
+    // `EXCEPTION_ATTRIBUTES` in the stub generator repeats what `to_pyerr` sets
+    check_stubs(cr#"
+raising = {
+    "FibonacciOverflowError": lambda: rust_lib.fibonacci_u128(187),
+    "InvalidModulusError": lambda: rust_lib.fibonacci_mod(1, 0),
+    "NegativeIndexError": lambda: rust_lib.FibonacciIterator(-1),
+    "ResourceLimitError": lambda: rust_lib.implementation(2**32),
+}
+exceptions = {name: stubbed for name, stubbed in classes.items() if issubclass(getattr(rust_lib, name), BaseException)}
+# the base class is never raised itself
+assert set(raising) == set(exceptions) - {"FibonacciError"}, set(raising) ^ set(exceptions)
+
+for name, raise_it in raising.items():
+    try:
+        raise_it()
+        raise AssertionError(f"{name} was not raised")
+    except getattr(rust_lib, name) as error:
+        assert type(error).__name__ == name, (name, error)
+        attributes = {node.target.id: node.annotation for node in exceptions[name].body if isinstance(node, ast.AnnAssign)}
+        assert set(vars(error)) == set(attributes), (name, vars(error), set(attributes))
+        for attribute, annotation in attributes.items():
+            assert conforms(getattr(error, attribute), annotation), (name, attribute)
+"#);
+}
+
+#[test]
+fn hand_written_annotations_match_the_results() {
+    // calls everything in `ANNOTATIONS` and `OVERLOADS` of the stub generator, checks that the arguments
+    // match the stubbed parameters and the result matches the return type of the first matching overload
+    check_stubs(cr#"
+import re
+
+encoder = rust_lib.FibonacciEncoder()
+examples = [
+    ("fibonacci_table", ()),
+    ("fibonacci_range", (0, 200)),
+    ("zeckendorf", (100,)),
+    ("zeckendorf", (100, False)),
+    ("zeckendorf", (100, True)),
+    ("from_zeckendorf", (0b1010,)),
+    ("from_zeckendorf", ([11, 6, 4],)),
+    ("fibonacci_encode", ([1, 2, 3],)),
+    ("fibonacci_encode", ((1, 2, 3),)),
+    ("FibonacciEncoder.write", (encoder, [1, 2, 3])),
+    ("FibonacciEncoder.write", (encoder, (4, 5))),
+]
+# the batch functions need NumPy, the others only get checked with lists and tuples without it
+if numpy is not None:
+    examples += [
+        ("fibonacci_batch", (numpy.arange(10),)),
+        ("fibonacci_batch", (numpy.arange(150, 190),)),
+        ("fibonacci_batch", (numpy.arange(180, 200),)),
+        ("fibonacci_encode", (numpy.array([1, 2, 3], dtype=numpy.uint16),)),
+        ("FibonacciEncoder.write", (encoder, numpy.array([6, 7], dtype=numpy.int64))),
+    ]
+
+def stubbed(path):
+    owner, _, name = path.rpartition(".")
+    return definitions(classes[owner].body)[0][name] if owner else functions[name]
+
+for path, arguments in examples:
+    result = functools.reduce(getattr, path.split("."), rust_lib)(*arguments)
+    matching = []
+    for function in stubbed(path):
+        annotations = {argument.arg: argument.annotation for argument in function.args.args}
+        try:
+            bound = signature(function).bind(*arguments).arguments
+        except TypeError:
+            continue
+        if all(name == "self" or conforms(value, annotations[name]) for name, value in bound.items()):
+            matching.append(function)
+    assert matching, (path, arguments)
+    returns = matching[0].returns
+    assert conforms(result, returns), (path, arguments, result, ast.unparse(returns))
+
+tables = re.search(r"const ANNOTATIONS.*?const EXCEPTION_ATTRIBUTES", stub_generator, re.DOTALL).group()
+keys = re.findall(r'\(\s*"([\w.]+)",', tables)
+called = {path for path, _ in examples}
+uncovered = [key for key in keys if key not in called and key.rpartition(".")[0] not in called]
+assert keys and (numpy is None or not uncovered), uncovered
+"#);
+}
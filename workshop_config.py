@@ -19,3 +19,8 @@ def print_result(result):
     Defines how to print a result for debugging
     """
     return result
+
+
+# The criterion benchmark in rust_lib/benches which does the same work as `do_work` in pure Rust.
+# Its results are compared to the calls through pyo3, to show how much the FFI costs.
+criterion_benchmark = "fibonacci/signed/180"